@@ -57,6 +57,7 @@ import dagger.hilt.android.lifecycle.HiltViewModel
 import dagger.hilt.components.SingletonComponent
 import io.ktor.client.HttpClient
 import io.ktor.client.engine.cio.CIO
+import kotlinx.coroutines.CompletableDeferred
 import kotlinx.coroutines.launch
 import java.time.ZoneOffset
 import java.time.ZonedDateTime
@@ -84,6 +85,24 @@ object AppModule {
     ): LocationTracker = DefaultLocationTracker(
         fusedLocationProviderClient = fusedLocationProviderClient, application = application
     )
+
+    @Provides
+    @Singleton
+    fun providesKeyValueStore(
+        application: Application
+    ): KeyValueStore = DefaultKeyValueStore(application = application)
+
+    @Provides
+    @Singleton
+    fun providesNotifier(
+        application: Application
+    ): Notifier = DefaultNotifier(application = application)
+
+    @Provides
+    @Singleton
+    fun providesRefreshScheduler(
+        application: Application
+    ): RefreshScheduler = DefaultRefreshScheduler(application = application)
 }
 
 @AndroidEntryPoint
@@ -104,29 +123,48 @@ class MainActivity : ComponentActivity() {
 }
 
 @HiltViewModel
-class Core @Inject constructor(
-    private val locationTracker: LocationTracker
+class CoreViewModel @Inject constructor(
+    val core: Core, refreshScheduler: RefreshScheduler
 ) : androidx.lifecycle.ViewModel() {
-    var view: ViewModel by mutableStateOf(
-        ViewModel(
-            Mode.National(), "", emptyList(), emptyList(), "", emptyList(), emptyList()
-        )
-    )
-        private set
-
-    private val httpClient = HttpClient(CIO)
-
     init {
         viewModelScope.launch {
-            update(Event.GetNational())
+            core.update(Event.Start())
+            core.update(Event.GetNational())
         }
+        refreshScheduler.start()
     }
+}
+
+// a singleton, so that the background refresh worker can share it with the UI
+@Singleton
+class Core @Inject constructor(
+    private val locationTracker: LocationTracker,
+    private val keyValueStore: KeyValueStore,
+    private val notifier: Notifier,
+    private val refreshScheduler: RefreshScheduler
+) {
+    // the core's view of its initial model, so this doesn't need to change
+    // whenever the view model does
+    var view: ViewModel by mutableStateOf(ViewModel.bincodeDeserialize(view()))
+        private set
+
+    private val httpClient = HttpClient(CIO)
+
+    private var refreshComplete: CompletableDeferred<Unit>? = null
 
     suspend fun update(event: Event) {
         val effects = processEvent(event.bincodeSerialize())
         processEffects(effects)
     }
 
+    // Refreshes the data, returning once the core says it's finished
+    suspend fun backgroundRefresh() {
+        val complete = CompletableDeferred<Unit>()
+        refreshComplete = complete
+        update(Event.BackgroundRefresh())
+        complete.await()
+    }
+
     private suspend fun processEffects(effects: ByteArray) {
         val requests = Requests.bincodeDeserialize(effects)
         for (request in requests) {
@@ -160,6 +198,25 @@ class Core @Inject constructor(
                 }
             }
 
+            is Effect.KeyValue -> {
+                val response = keyValueStore.request(effect.value)
+
+                val effects =
+                    handleResponse(request.uuid.toByteArray(), response.bincodeSerialize())
+
+                processEffects(effects)
+            }
+
+            is Effect.Notify -> {
+                notifier.request(effect.value)
+            }
+
+            is Effect.Background -> {
+                refreshScheduler.schedule(effect.value)
+                refreshComplete?.complete(Unit)
+                refreshComplete = null
+            }
+
             is Effect.Time -> {
                 val response =
                     TimeResponse(
@@ -177,7 +234,8 @@ class Core @Inject constructor(
 
 @OptIn(ExperimentalPermissionsApi::class)
 @Composable
-fun View(core: Core = viewModel()) {
+fun View(model: CoreViewModel = viewModel()) {
+    val core = model.core
     val coroutineScope = rememberCoroutineScope()
 
     val locationPermissions = rememberMultiplePermissionsState(
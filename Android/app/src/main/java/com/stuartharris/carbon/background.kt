@@ -0,0 +1,70 @@
+package com.stuartharris.carbon
+
+import android.app.Application
+import android.content.Context
+import androidx.work.Constraints
+import androidx.work.CoroutineWorker
+import androidx.work.ExistingWorkPolicy
+import androidx.work.NetworkType
+import androidx.work.OneTimeWorkRequestBuilder
+import androidx.work.WorkManager
+import androidx.work.WorkerParameters
+import com.stuartharris.carbon.shared_types.RefreshComplete
+import dagger.hilt.EntryPoint
+import dagger.hilt.InstallIn
+import dagger.hilt.android.EntryPointAccessors
+import dagger.hilt.components.SingletonComponent
+import java.time.Duration
+import java.time.Instant
+import java.time.ZonedDateTime
+
+private const val REFRESH_WORK = "refresh"
+
+interface RefreshScheduler {
+    // Schedules the first refresh, unless there's one already
+    fun start()
+
+    // Schedules the next refresh, once the core has finished this one
+    fun schedule(complete: RefreshComplete)
+}
+
+class DefaultRefreshScheduler(
+    private val application: Application
+) : RefreshScheduler {
+    override fun start() {
+        enqueue(Duration.ZERO, ExistingWorkPolicy.KEEP)
+    }
+
+    override fun schedule(complete: RefreshComplete) {
+        val next = ZonedDateTime.parse(complete.next_refresh).toInstant()
+        // the worker that's running is still enqueued, so append to it
+        enqueue(Duration.between(Instant.now(), next), ExistingWorkPolicy.APPEND_OR_REPLACE)
+    }
+
+    private fun enqueue(delay: Duration, policy: ExistingWorkPolicy) {
+        val request = OneTimeWorkRequestBuilder<RefreshWorker>()
+            .setInitialDelay(maxOf(delay, Duration.ZERO))
+            .setConstraints(
+                Constraints.Builder().setRequiredNetworkType(NetworkType.CONNECTED).build()
+            )
+            .build()
+        WorkManager.getInstance(application).enqueueUniqueWork(REFRESH_WORK, policy, request)
+    }
+}
+
+class RefreshWorker(
+    context: Context, params: WorkerParameters
+) : CoroutineWorker(context, params) {
+    @EntryPoint
+    @InstallIn(SingletonComponent::class)
+    interface CoreEntryPoint {
+        fun core(): Core
+    }
+
+    override suspend fun doWork(): Result {
+        EntryPointAccessors.fromApplication(applicationContext, CoreEntryPoint::class.java)
+            .core()
+            .backgroundRefresh()
+        return Result.success()
+    }
+}
@@ -71,13 +71,15 @@ fun MixChart(
                         } else {
                             val cumulative = acc.first + element.perc
                             val newElement =
-                                GenerationMixPoint(element.date, element.fuel, cumulative)
+                                GenerationMixPoint(
+                                    element.date, element.fuel, element.label, cumulative, element.grams
+                                )
                             cumulative to acc.second + newElement
                         }
                     }
 
                 val groups = stacked.values.flatMap { it.second }.groupBy {
-                    it.fuel
+                    it.label
                 }
 
                 val firstSet = groups.values.first()
@@ -0,0 +1,35 @@
+package com.stuartharris.carbon
+
+import android.app.Application
+import android.content.Context
+import android.util.Base64
+import com.stuartharris.carbon.shared_types.KeyValueRequest
+import com.stuartharris.carbon.shared_types.KeyValueResponse
+import java.util.Optional
+
+interface KeyValueStore {
+    fun request(request: KeyValueRequest): KeyValueResponse
+}
+
+class DefaultKeyValueStore(
+    application: Application
+) : KeyValueStore {
+    private val preferences =
+        application.getSharedPreferences("carbon_intensity", Context.MODE_PRIVATE)
+
+    override fun request(request: KeyValueRequest): KeyValueResponse = when (request) {
+        is KeyValueRequest.Read -> {
+            val value = preferences.getString(request.value, null)
+            KeyValueResponse.Read(Optional.ofNullable(value?.let {
+                Base64.decode(it, Base64.DEFAULT).toList()
+            }))
+        }
+
+        is KeyValueRequest.Write -> {
+            val value = Base64.encodeToString(request.field1.toByteArray(), Base64.DEFAULT)
+            KeyValueResponse.Write(preferences.edit().putString(request.field0, value).commit())
+        }
+
+        else -> throw IllegalArgumentException("unknown key-value request: $request")
+    }
+}
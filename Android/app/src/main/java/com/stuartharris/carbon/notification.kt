@@ -0,0 +1,74 @@
+package com.stuartharris.carbon
+
+import android.app.AlarmManager
+import android.app.Application
+import android.app.NotificationChannel
+import android.app.NotificationManager
+import android.app.PendingIntent
+import android.content.BroadcastReceiver
+import android.content.Context
+import android.content.Intent
+import android.net.Uri
+import androidx.core.app.NotificationCompat
+import com.stuartharris.carbon.shared_types.Notification
+import com.stuartharris.carbon.shared_types.NotificationRequest
+import java.time.ZonedDateTime
+
+private const val CHANNEL = "thresholds"
+private const val TITLE = "title"
+private const val BODY = "body"
+
+interface Notifier {
+    fun request(request: NotificationRequest)
+}
+
+class DefaultNotifier(
+    private val application: Application
+) : Notifier {
+    private val alarmManager = application.getSystemService(Context.ALARM_SERVICE) as AlarmManager
+
+    override fun request(request: NotificationRequest) {
+        when (request) {
+            is NotificationRequest.Schedule -> {
+                val notification = request.value
+                val at = ZonedDateTime.parse(notification.at).toInstant().toEpochMilli()
+                alarmManager.set(
+                    AlarmManager.RTC_WAKEUP, at, pendingIntent(notification.id, notification)
+                )
+            }
+
+            is NotificationRequest.Cancel -> alarmManager.cancel(pendingIntent(request.value))
+        }
+    }
+
+    // the intent's data identifies the alarm, so that it can be replaced or cancelled
+    private fun pendingIntent(id: String, notification: Notification? = null): PendingIntent {
+        val intent = Intent(application, NotificationReceiver::class.java).apply {
+            data = Uri.fromParts("notification", id, null)
+            if (notification != null) {
+                putExtra(TITLE, notification.title)
+                putExtra(BODY, notification.body)
+            }
+        }
+        return PendingIntent.getBroadcast(
+            application, 0, intent, PendingIntent.FLAG_UPDATE_CURRENT or PendingIntent.FLAG_IMMUTABLE
+        )
+    }
+}
+
+class NotificationReceiver : BroadcastReceiver() {
+    override fun onReceive(context: Context, intent: Intent) {
+        val manager = context.getSystemService(NotificationManager::class.java)
+        manager.createNotificationChannel(
+            NotificationChannel(CHANNEL, "Thresholds", NotificationManager.IMPORTANCE_DEFAULT)
+        )
+
+        val notification = NotificationCompat.Builder(context, CHANNEL)
+            .setSmallIcon(R.mipmap.ic_launcher)
+            .setContentTitle(intent.getStringExtra(TITLE))
+            .setContentText(intent.getStringExtra(BODY))
+            .setAutoCancel(true)
+            .build()
+        manager.notify(intent.data.toString(), 0, notification)
+    }
+}
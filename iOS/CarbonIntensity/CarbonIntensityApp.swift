@@ -2,9 +2,14 @@ import SwiftUI
 
 @main
 struct CarbonIntensityApp: App {
+    @StateObject private var model = Model()
+
     var body: some Scene {
         WindowGroup {
-            ContentView(model: Model())
+            ContentView(model: model)
+        }
+        .backgroundTask(.appRefresh(refreshTaskIdentifier)) {
+            await model.backgroundRefresh()
         }
     }
 }
@@ -4,18 +4,24 @@ import SwiftUI
 
 @MainActor
 class Model: ObservableObject {
-    @Published var view = ViewModel(
-        mode: .national,
-        national_name: "",
-        national_intensity: [],
-        national_mix: [],
-        local_name: "",
-        local_intensity: [],
-        local_mix: []
-    )
+    // the core's view of its initial model, so this doesn't need to change
+    // whenever the view model does
+    @Published var view: ViewModel = try! .bincodeDeserialize(input: [UInt8](CarbonIntensity.view()))
+
+    private var refreshComplete: CheckedContinuation<Void, Never>?
 
     init() {
+        update(event: .start)
         update(event: .getNational)
+        scheduleRefresh()
+    }
+
+    /// Refreshes the data, returning once the core says it's finished
+    func backgroundRefresh() async {
+        await withCheckedContinuation { continuation in
+            refreshComplete = continuation
+            update(event: .backgroundRefresh)
+        }
     }
 
     func update(event: Event) {
@@ -54,6 +60,18 @@ class Model: ObservableObject {
             let effects = [UInt8](handleResponse(Data(request.uuid), Data(try! response.bincodeSerialize())))
 
             process_effects(effects)
+        case let .keyValue(req):
+            let response = keyValueRequest(req)
+
+            let effects = [UInt8](handleResponse(Data(request.uuid), Data(try! response.bincodeSerialize())))
+
+            process_effects(effects)
+        case let .notify(req):
+            notificationRequest(req)
+        case let .background(req):
+            backgroundRequest(req)
+            refreshComplete?.resume()
+            refreshComplete = nil
         }
     }
 }
@@ -145,7 +163,7 @@ struct ContentView: View {
                     x: .value("Time", $0.date),
                     y: .value("Percent", $0.perc)
                 ).opacity(0.5)
-                    .foregroundStyle(by: .value("Fuel", $0.fuel))
+                    .foregroundStyle(by: .value("Fuel", $0.label))
             }
             .frame(height: 250)
             .chartYScale(domain: 0 ... 100)
@@ -0,0 +1,14 @@
+import BackgroundTasks
+import SharedTypes
+
+let refreshTaskIdentifier = "com.example.carbonintensity.refresh"
+
+func scheduleRefresh(at date: Date? = nil) {
+    let request = BGAppRefreshTaskRequest(identifier: refreshTaskIdentifier)
+    request.earliestBeginDate = date
+    try? BGTaskScheduler.shared.submit(request)
+}
+
+func backgroundRequest(_ request: RefreshComplete) {
+    scheduleRefresh(at: ISO8601DateFormatter().date(from: request.next_refresh))
+}
@@ -0,0 +1,13 @@
+import Foundation
+import SharedTypes
+
+func keyValueRequest(_ request: KeyValueRequest) -> KeyValueResponse {
+    let defaults = UserDefaults.standard
+    switch request {
+    case let .read(key):
+        return .read(defaults.data(forKey: key).map { [UInt8]($0) })
+    case let .write(key, value):
+        defaults.set(Data(value), forKey: key)
+        return .write(true)
+    }
+}
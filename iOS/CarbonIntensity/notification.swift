@@ -0,0 +1,27 @@
+import Foundation
+import SharedTypes
+import UserNotifications
+
+func notificationRequest(_ request: NotificationRequest) {
+    let center = UNUserNotificationCenter.current()
+    switch request {
+    case let .schedule(notification):
+        center.requestAuthorization(options: [.alert, .sound]) { granted, _ in
+            guard granted else { return }
+
+            let content = UNMutableNotificationContent()
+            content.title = notification.title
+            content.body = notification.body
+
+            // a trigger needs a positive interval, so show past ones straight away
+            let at = ISO8601DateFormatter().date(from: notification.at) ?? Date()
+            let trigger = UNTimeIntervalNotificationTrigger(
+                timeInterval: max(at.timeIntervalSinceNow, 1),
+                repeats: false
+            )
+            center.add(UNNotificationRequest(identifier: notification.id, content: content, trigger: trigger))
+        }
+    case let .cancel(id):
+        center.removePendingNotificationRequests(withIdentifiers: [id])
+    }
+}
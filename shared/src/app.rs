@@ -1,4 +1,4 @@
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use crux_core::render::Render;
 use crux_http::Http;
 use crux_macros::Effect;
@@ -6,12 +6,30 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     capabilities::{
+        background::{Background, RefreshComplete},
+        key_value::KeyValue,
         location::{GetLocation, LocationResponse},
+        notification::{Notification, Notify},
         time::{Time, TimeResponse},
     },
     model::{
+        error::{self, Error},
+        factors,
+        footprint::Usage,
+        history::{self, HistoryRange},
+        horizon::Horizon,
+        intensity,
         location::{Coordinate, Location},
-        national_intensity, national_mix, postcode, regional, Mode, Model,
+        national_intensity, national_mix,
+        postcode::{self, Search},
+        refresh::{self, RefreshPolicy},
+        regional,
+        scheduling::Job,
+        sites::{self, Site, SiteForecast},
+        storage,
+        thresholds::{Notified, Threshold},
+        zone::Zone,
+        Data, DataSet, Local, Mode, Model, Operation, Place, SavedLocation, Sites,
     },
     view_model::ViewModel,
 };
@@ -20,22 +38,152 @@ use crate::{
 pub enum Event {
     GetNational,
     GetLocal,
+    GetRegions,
+    /// Shows the local and national forecasts side by side
+    GetComparison,
+    SelectRegion(Option<i64>),
+    /// The national intensity over the past 1 to 30 days
+    GetHistory(u32),
+    GetHistoryForDate(String),
+    ScheduleJob {
+        duration_minutes: i64,
+        deadline: String,
+    },
+    ClearJob,
+    CalculateFootprint {
+        energy_kwh: f64,
+        from: String,
+        to: String,
+    },
+    ClearFootprint,
+    EnterPostcode(String),
+    UseDeviceLocation,
+    AddLocation {
+        name: String,
+        postcode: String,
+    },
+    RemoveLocation(String),
+    MoveLocation {
+        name: String,
+        index: u32,
+    },
+    SelectLocation(Option<String>),
+    /// Restores the model saved by a previous run of the app
+    Start,
+    SetRefreshPolicy(RefreshPolicy),
+    /// Fetches the data for `scope` if the refresh policy says it's due, or
+    /// regardless of the policy if `force` is set. For local data, this
+    /// finds the location again too, in case the user has moved.
+    Refresh {
+        scope: Mode,
+        force: bool,
+    },
+    /// Sets how far ahead to fetch forecasts, and fetches the forecasts for
+    /// the current mode again
+    SetHorizon(Horizon),
+    /// Sets the time zone that times are shown in
+    SetZone(Zone),
+    /// Adds a rule for notifying the user about the forecast, replacing any
+    /// with the same name
+    AddThreshold(Threshold),
+    RemoveThreshold(String),
+    /// Compares the forecasts for two places, each a postcode, an outcode or
+    /// a DNO region ID (1 to 14), and the emissions of a constant load (kW)
+    /// in each
+    CompareSites {
+        first: String,
+        second: String,
+        load_kw: f64,
+    },
+    ClearSites,
+    /// Fetches the national and local data if they're due, without
+    /// rendering, for the shell's background task. The shell is told when
+    /// it's finished, whether anything changed and when to refresh next.
+    BackgroundRefresh,
 
     // events local to the core
     #[serde(skip)]
+    Load(Option<Vec<u8>>),
+    #[serde(skip)]
     SetTimeLocal(TimeResponse),
     #[serde(skip)]
     SetTimeNational(TimeResponse),
     #[serde(skip)]
-    SetLocation(LocationResponse),
+    SetTimeRegions(TimeResponse),
+    #[serde(skip)]
+    SetTimeHistory(TimeResponse),
+    #[serde(skip)]
+    SetTimeBackground(TimeResponse),
+    #[serde(skip)]
+    SetTimeSites(TimeResponse),
+    #[serde(skip)]
+    SetTimeRefresh(Mode, bool, TimeResponse),
+    // responses carry the generation of the data they were requested for
+    // (see `Data::generation`)
+    #[serde(skip)]
+    SetLocation(u64, LocationResponse),
+    #[serde(skip)]
+    SetPostcode(u64, Result<postcode::PostcodeResponse, Error>),
+    #[serde(skip)]
+    SetLookup(Place, u64, Result<postcode::LookupResponse, Error>),
+    #[serde(skip)]
+    SetRegional(Place, u64, Result<regional::RegionalResponse, Error>),
+    #[serde(skip)]
+    SetNational(u64, Result<national_intensity::NationalResponse, Error>),
     #[serde(skip)]
-    SetPostcode(crux_http::Result<crux_http::Response<postcode::PostcodeResponse>>),
+    SetNationalMix(u64, Result<national_mix::NationalMixResponse, Error>),
     #[serde(skip)]
-    SetRegional(crux_http::Result<crux_http::Response<regional::RegionalResponse>>),
+    SetNationalPast(u64, Result<national_intensity::NationalResponse, Error>),
     #[serde(skip)]
-    SetNational(crux_http::Result<crux_http::Response<national_intensity::NationalResponse>>),
+    SetRegionalPast(Place, u64, Result<regional::RegionalResponse, Error>),
     #[serde(skip)]
-    SetNationalMix(crux_http::Result<crux_http::Response<national_mix::NationalMixResponse>>),
+    SetRegionPast(u64, Result<regional::RegionalResponse, Error>),
+    #[serde(skip)]
+    SetRegions(Result<regional::RegionsResponse, Error>),
+    #[serde(skip)]
+    SetRegion(u64, Result<regional::RegionalResponse, Error>),
+    #[serde(skip)]
+    SetSite(usize, u64, Result<regional::RegionalResponse, Error>),
+    #[serde(skip)]
+    SetFactors(Result<factors::Root, Error>),
+    #[serde(skip)]
+    SetHistory(
+        HistoryRange,
+        u64,
+        Result<national_intensity::NationalResponse, Error>,
+    ),
+}
+
+impl Event {
+    /// Whether the shell sent the event, rather than the core in response to
+    /// a request
+    fn is_from_shell(&self) -> bool {
+        !matches!(
+            self,
+            Event::Load(_)
+                | Event::SetTimeLocal(_)
+                | Event::SetTimeNational(_)
+                | Event::SetTimeRegions(_)
+                | Event::SetTimeHistory(_)
+                | Event::SetTimeBackground(_)
+                | Event::SetTimeSites(_)
+                | Event::SetTimeRefresh(..)
+                | Event::SetLocation(..)
+                | Event::SetPostcode(..)
+                | Event::SetLookup(..)
+                | Event::SetRegional(..)
+                | Event::SetNational(..)
+                | Event::SetNationalMix(..)
+                | Event::SetNationalPast(..)
+                | Event::SetRegionalPast(..)
+                | Event::SetRegionPast(..)
+                | Event::SetRegions(_)
+                | Event::SetRegion(..)
+                | Event::SetSite(..)
+                | Event::SetFactors(_)
+                | Event::SetHistory(..)
+        )
+    }
 }
 
 #[cfg_attr(feature = "typegen", derive(crux_macros::Export))]
@@ -45,6 +193,9 @@ pub struct Capabilities {
     location: GetLocation<Event>,
     time: Time<Event>,
     http: Http<Event>,
+    key_value: KeyValue<Event>,
+    notify: Notify<Event>,
+    background: Background<Event>,
 }
 
 #[derive(Default)]
@@ -57,102 +208,640 @@ impl crux_core::App for App {
     type Capabilities = Capabilities;
 
     fn update(&self, event: Self::Event, model: &mut Self::Model, caps: &Self::Capabilities) {
+        if is_superseded(&event, model) {
+            return;
+        }
+        if let Some(background) = &mut model.background {
+            if event.is_from_shell() && event != Event::BackgroundRefresh {
+                background.foreground = true;
+            }
+        }
+
         match event {
+            Event::Start => caps.key_value.read(storage::KEY, Event::Load),
+            Event::Load(Some(bytes)) => {
+                // if the stored model can't be restored, just start afresh
+                if let Ok(stored) = storage::load(&bytes) {
+                    model.restore(stored);
+                }
+                render(model, caps);
+            }
+            Event::Load(None) => render(model, caps),
+            Event::AddThreshold(threshold) => match threshold.validate() {
+                Ok(()) => {
+                    model.threshold_error = None;
+                    cancel_notifications(&threshold.name, model, caps);
+                    match model
+                        .thresholds
+                        .iter_mut()
+                        .find(|existing| existing.name == threshold.name)
+                    {
+                        Some(existing) => *existing = threshold,
+                        None => model.thresholds.push(threshold),
+                    }
+                    notify(model, caps);
+                    save(model, caps);
+                    render(model, caps);
+                }
+                Err(e) => {
+                    model.threshold_error = Some(e);
+                    render(model, caps);
+                }
+            },
+            Event::RemoveThreshold(name) => {
+                cancel_notifications(&name, model, caps);
+                model.thresholds.retain(|threshold| threshold.name != name);
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::BackgroundRefresh => {
+                if model.background.is_none() {
+                    model.background = Some(refresh::Background {
+                        national: model.national.periods.clone(),
+                        local: model.selected().periods.clone(),
+                        foreground: false,
+                    });
+                }
+                if !model.national.is_pending(Operation::Time) {
+                    model.national.start(Operation::Time);
+                    caps.time.get(Event::SetTimeBackground);
+                }
+            }
+            Event::SetTimeBackground(time) => {
+                model.national.finish(Operation::Time);
+                set_time(time, model);
+                refresh_national(model, caps, false);
+                // don't ask for the device's location unless the user has
+                // already looked at local data
+                if model.selected().has_place() {
+                    refresh_local(model, caps, false);
+                }
+            }
+            Event::SetRefreshPolicy(policy) => {
+                model.refresh = policy.clamped();
+                save(model, caps);
+            }
+            Event::Refresh { scope, force } => refresh(scope, force, model, caps),
+            Event::SetZone(zone) => {
+                model.zone = zone;
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetHorizon(horizon) => {
+                if model.horizon == horizon {
+                    render(model, caps);
+                } else {
+                    model.horizon = horizon;
+                    // the forecasts we have cover the wrong length of time
+                    let generation = model.next_generation();
+                    model.national.clear(generation);
+                    model.local.clear(generation);
+                    model.regions.clear(generation);
+                    model.sites.clear(generation);
+                    for saved in &mut model.saved {
+                        saved.data.clear(generation);
+                    }
+                    model.national.scope.generation_mix.clear();
+                    for forecast in &mut model.sites.scope.forecasts {
+                        forecast.periods.clear();
+                    }
+                    save(model, caps);
+                    refresh(model.mode, false, model, caps);
+                    if !model.sites.scope.forecasts.is_empty() {
+                        get_time(model, |model| &mut model.sites, caps, Event::SetTimeSites);
+                    }
+                }
+            }
             Event::GetNational => {
                 model.mode = Mode::National;
-                caps.time.get(Event::SetTimeNational);
+                get_time(
+                    model,
+                    |model| &mut model.national,
+                    caps,
+                    Event::SetTimeNational,
+                );
             }
             Event::GetLocal => {
                 model.mode = Mode::Local;
-                caps.time.get(Event::SetTimeLocal);
+                get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
             }
-            Event::SetTimeLocal(TimeResponse(iso_time)) => {
-                let current_time = DateTime::parse_from_rfc3339(&iso_time)
-                    .unwrap()
-                    .with_timezone(&Utc);
-                model.time = current_time;
+            Event::GetRegions => {
+                model.mode = Mode::Regions;
+                get_time(
+                    model,
+                    |model| &mut model.regions,
+                    caps,
+                    Event::SetTimeRegions,
+                );
+            }
+            Event::GetComparison => {
+                model.mode = Mode::Compare;
+                get_time(
+                    model,
+                    |model| &mut model.national,
+                    caps,
+                    Event::SetTimeNational,
+                );
+                get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
+            }
+            Event::CompareSites {
+                first,
+                second,
+                load_kw,
+            } => match sites::check_load(load_kw)
+                .and_then(|_| Ok((Site::parse(&first)?, Site::parse(&second)?)))
+            {
+                Ok((first, second)) => {
+                    let sites = &mut model.sites.scope;
+                    let unchanged = sites.forecasts.len() == 2
+                        && sites.forecasts[0].site == first
+                        && sites.forecasts[1].site == second;
+                    sites.load_kw = load_kw;
+                    if !unchanged {
+                        sites.forecasts = vec![SiteForecast::new(first), SiteForecast::new(second)];
+                        let generation = model.next_generation();
+                        model.sites.clear(generation);
+                    }
+                    get_time(model, |model| &mut model.sites, caps, Event::SetTimeSites);
+                }
+                Err(e) => {
+                    model.sites.error = Some(e);
+                    render(model, caps);
+                }
+            },
+            Event::ClearSites => {
+                model.sites.scope = Sites::default();
+                let generation = model.next_generation();
+                model.sites.clear(generation);
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SelectRegion(region_id) => {
+                model.mode = Mode::Regions;
+                if model.regions.scope.selected != region_id {
+                    model.regions.scope.selected = region_id;
+                    model.regions.scope.region_error = None;
+                    let generation = model.next_generation();
+                    model.regions.clear(generation);
+                }
+                get_time(
+                    model,
+                    |model| &mut model.regions,
+                    caps,
+                    Event::SetTimeRegions,
+                );
+            }
+            Event::GetHistory(days) => match HistoryRange::days(days) {
+                Ok(range) => {
+                    set_history_range(range, model);
+                    get_time(
+                        model,
+                        |model| &mut model.history,
+                        caps,
+                        Event::SetTimeHistory,
+                    );
+                }
+                Err(e) => {
+                    model.history.error = Some(e);
+                    render(model, caps);
+                }
+            },
+            Event::GetHistoryForDate(date) => {
+                match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+                    Ok(date) => {
+                        set_history_range(HistoryRange::Date(date), model);
+                        get_time(
+                            model,
+                            |model| &mut model.history,
+                            caps,
+                            Event::SetTimeHistory,
+                        );
+                    }
+                    Err(_) => {
+                        model.history.error = Some(Error::InvalidDate(date));
+                        render(model, caps);
+                    }
+                }
+            }
+            Event::ScheduleJob {
+                duration_minutes,
+                deadline,
+            } => {
+                match Job::parse(duration_minutes, &deadline, model.time, model.horizon) {
+                    Ok(job) => {
+                        model.job = Some(job);
+                        model.job_error = None;
+                    }
+                    Err(e) => {
+                        model.job = None;
+                        model.job_error = Some(e);
+                    }
+                }
+                render(model, caps);
+            }
+            Event::ClearJob => {
+                model.job = None;
+                model.job_error = None;
+                render(model, caps);
+            }
+            Event::CalculateFootprint {
+                energy_kwh,
+                from,
+                to,
+            } => {
+                match Usage::parse(energy_kwh, &from, &to) {
+                    Ok(usage) => {
+                        model.usage = Some(usage);
+                        model.usage_error = None;
+                    }
+                    Err(e) => {
+                        model.usage = None;
+                        model.usage_error = Some(e);
+                    }
+                }
+                render(model, caps);
+            }
+            Event::ClearFootprint => {
+                model.usage = None;
+                model.usage_error = None;
+                render(model, caps);
+            }
+            Event::EnterPostcode(text) => {
+                model.mode = Mode::Local;
+                model.place = Place::Current;
+                match Search::parse(&text) {
+                    Ok(search) => {
+                        model.local.scope.search = Some(search);
+                        model.local.scope.location = None;
+                        let generation = model.next_generation();
+                        model.local.clear(generation);
+                        get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
+                    }
+                    Err(e) => {
+                        model.local.error = Some(e);
+                        render(model, caps);
+                    }
+                }
+            }
+            Event::UseDeviceLocation => {
+                model.mode = Mode::Local;
+                model.place = Place::Current;
+                model.local.scope.search = None;
+                model.local.scope.location = None;
+                let generation = model.next_generation();
+                model.local.clear(generation);
+                get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
+            }
+            Event::AddLocation { name, postcode } => match Search::parse(&postcode) {
+                Ok(search) => {
+                    // a new generation, so that responses for the location's
+                    // old postcode (or a removed location of the same name)
+                    // are ignored
+                    let data = Data {
+                        scope: Local {
+                            location: None,
+                            search: Some(search),
+                        },
+                        generation: model.next_generation(),
+                        ..Default::default()
+                    };
+                    let place = Place::Saved(name.clone());
+                    match model.local_mut(&place) {
+                        Some(existing) => *existing = data,
+                        None => model.saved.push(SavedLocation { name, data }),
+                    }
 
-                if current_time - model.local.last_updated > Duration::minutes(30) {
-                    caps.location.get(Event::SetLocation);
-                } else {
-                    caps.render.render();
+                    model.mode = Mode::Local;
+                    model.place = place;
+                    get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
                 }
+                Err(e) => {
+                    if let Some(local) = model.local_mut(&model.place.clone()) {
+                        local.error = Some(e);
+                    }
+                    render(model, caps);
+                }
+            },
+            Event::RemoveLocation(name) => {
+                model.saved.retain(|saved| saved.name != name);
+                if model.place == Place::Saved(name) {
+                    model.place = Place::Current;
+                }
+                save(model, caps);
+                render(model, caps);
             }
-            Event::SetTimeNational(TimeResponse(iso_time)) => {
-                let current_time = DateTime::parse_from_rfc3339(&iso_time)
-                    .unwrap()
-                    .with_timezone(&Utc);
-                model.time = current_time;
-
-                if current_time - model.national.last_updated > Duration::minutes(30) {
-                    caps.http
-                        .get(national_intensity::url(&model.time))
-                        .expect_json()
-                        .send(Event::SetNational);
-                    caps.http
-                        .get(national_mix::url(&model.time))
-                        .expect_json()
-                        .send(Event::SetNationalMix);
-                } else {
-                    caps.render.render();
+            Event::MoveLocation { name, index } => {
+                if let Some(from) = model.saved.iter().position(|saved| saved.name == name) {
+                    let saved = model.saved.remove(from);
+                    let to = (index as usize).min(model.saved.len());
+                    model.saved.insert(to, saved);
+                }
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SelectLocation(name) => {
+                let place = name.map(Place::Saved).unwrap_or_default();
+                if model.local(&place).is_some() {
+                    model.place = place;
+                }
+                model.mode = Mode::Local;
+                get_time(model, Model::selected_mut, caps, Event::SetTimeLocal);
+            }
+            Event::SetTimeLocal(time) => {
+                set_time(time, model);
+                model.selected_mut().finish(Operation::Time);
+                refresh_local(model, caps, false);
+            }
+            Event::SetTimeNational(time) => {
+                set_time(time, model);
+                model.national.finish(Operation::Time);
+                refresh_national(model, caps, false);
+            }
+            Event::SetTimeRegions(time) => {
+                set_time(time, model);
+                model.regions.finish(Operation::Time);
+                refresh_regions(model, caps, false);
+            }
+            Event::SetTimeHistory(time) => {
+                set_time(time, model);
+                model.history.finish(Operation::Time);
+                refresh_history(model, caps, false);
+            }
+            Event::SetTimeSites(time) => {
+                set_time(time, model);
+                model.sites.finish(Operation::Time);
+                refresh_sites(model, caps, false);
+            }
+            Event::SetTimeRefresh(scope, force, time) => {
+                set_time(time, model);
+                match scope {
+                    Mode::National => {
+                        model.national.finish(Operation::Time);
+                        refresh_national(model, caps, force);
+                    }
+                    Mode::Local => {
+                        model.selected_mut().finish(Operation::Time);
+                        refresh_local(model, caps, force);
+                    }
+                    Mode::Regions => {
+                        model.regions.finish(Operation::Time);
+                        refresh_regions(model, caps, force);
+                    }
+                    Mode::Compare => {
+                        model.national.finish(Operation::Time);
+                        model.selected_mut().finish(Operation::Time);
+                        refresh_national(model, caps, force);
+                        refresh_local(model, caps, force);
+                    }
                 }
             }
-            Event::SetLocation(LocationResponse {
-                location: Some(location),
-            }) => {
+            Event::SetLocation(
+                generation,
+                LocationResponse {
+                    location: Some(location),
+                },
+            ) => {
                 caps.http
                     .get(postcode::url())
                     .query(&postcode::Query::from(location))
                     .unwrap()
-                    .expect_json()
-                    .send(Event::SetPostcode);
-                caps.render.render();
-            }
-            Event::SetLocation(LocationResponse { location: None }) => {}
-            Event::SetPostcode(Ok(mut postcode)) => {
-                let postcode = postcode.take_body().unwrap();
-                let postcode = postcode.result[0].clone(); // TODO error handling
-                let outcode = postcode.outcode;
-                let url = regional::url(&model.time, &outcode);
-
-                model.local.scope.location = Some(Location {
+                    .send(move |response| Event::SetPostcode(generation, error::decode(response)));
+                model.local.finish(Operation::Location);
+                model.local.start(Operation::Postcode);
+                render(model, caps);
+            }
+            Event::SetLocation(_, LocationResponse { location: None }) => {
+                model.local.error = Some(Error::LocationDenied);
+                model.local.finish(Operation::Location);
+                render(model, caps);
+            }
+            Event::SetPostcode(_, Ok(response)) => {
+                model.local.finish(Operation::Postcode);
+                match response.nearest() {
+                    Some(postcode) => {
+                        let location = Location {
+                            coordinate: Coordinate {
+                                latitude: postcode.latitude,
+                                longitude: postcode.longitude,
+                            },
+                            outcode: postcode.outcode,
+                            admin_district: postcode.admin_district,
+                        };
+                        get_regional(Place::Current, location, model, caps);
+                    }
+                    None => {
+                        model.local.error = Some(Error::NoPostcode);
+                        render(model, caps);
+                    }
+                }
+            }
+            Event::SetLookup(place, _, Ok(response)) => {
+                let lookup = response.result;
+                let location = Location {
                     coordinate: Coordinate {
-                        latitude: postcode.latitude,
-                        longitude: postcode.longitude,
+                        latitude: lookup.latitude.unwrap_or_default(),
+                        longitude: lookup.longitude.unwrap_or_default(),
                     },
-                    outcode,
-                    admin_district: postcode.admin_district.clone(),
-                });
+                    outcode: lookup.outcode,
+                    admin_district: lookup.admin_district.join(", "),
+                };
+
+                if let Some(local) = model.local_mut(&place) {
+                    local.finish(Operation::Lookup);
+                }
+                get_regional(place, location, model, caps);
+            }
+            Event::SetLookup(place, _, Err(Error::Status(404))) => {
+                if let Some(local) = model.local_mut(&place) {
+                    let search = local.scope.search.as_ref();
+                    let code = search.map(ToString::to_string).unwrap_or_default();
+                    local.error = Some(Error::InvalidPostcode(code));
+                    local.finish(Operation::Lookup);
+                }
+                render(model, caps);
+            }
+            Event::SetPostcode(_, Err(e)) => {
+                model.local.error = Some(e);
+                model.local.failures.record(model.time);
+                model.local.finish(Operation::Postcode);
+                render(model, caps);
+            }
+            Event::SetLookup(place, _, Err(e)) => {
+                set_local_error(&place, e, Operation::Lookup, model);
+                render(model, caps);
+            }
+            Event::SetRegional(place, _, Err(e)) => {
+                set_local_error(&place, e, Operation::Regional, model);
+                render(model, caps);
+            }
+            Event::SetRegional(place, _, Ok(regional)) => {
+                let time = model.time;
+                if let Some(local) = model.local_mut(&place) {
+                    local.periods = regional.data.data;
+                    local.last_updated = time;
+                    local.error = None;
+                    local.failures.reset();
+                    local.finish(Operation::Regional);
+                }
 
-                caps.http.get(url).expect_json().send(Event::SetRegional);
-                caps.render.render();
+                notify(model, caps);
+                save(model, caps);
+                render(model, caps);
             }
-            Event::SetPostcode(Err(_)) => {}
-            Event::SetRegional(Ok(mut response)) => {
-                let regional = response.take_body().unwrap();
-                model.local.periods = regional.data.data.clone();
-                model.local.last_updated = model.time;
+            Event::SetNational(_, Ok(national)) => {
+                model.national.periods = national.data;
+                intensity::attach_mix(
+                    &mut model.national.periods,
+                    &model.national.scope.generation_mix,
+                );
+                model.national.scope.intensity_error = None;
+                finish_national(Operation::NationalIntensity, model);
 
-                caps.render.render();
+                notify(model, caps);
+                save(model, caps);
+                render(model, caps);
             }
-            Event::SetRegional(Err(_)) => {}
-            Event::SetNational(Ok(mut response)) => {
-                let national = response.take_body().unwrap();
-                model.national.periods = national.data.clone();
-                model.national.last_updated = model.time;
+            Event::SetNationalMix(_, Ok(national)) => {
+                model.national.scope.generation_mix = national.data;
+                intensity::attach_mix(
+                    &mut model.national.periods,
+                    &model.national.scope.generation_mix,
+                );
+                model.national.scope.mix_error = None;
+                finish_national(Operation::NationalMix, model);
 
-                caps.render.render();
+                save(model, caps);
+                render(model, caps);
             }
-            Event::SetNational(Err(_)) => {}
-            Event::SetNationalMix(Ok(mut response)) => {
-                let national = response.take_body().unwrap();
-                model.national.scope.generation_mix = national.data.clone();
-                model.national.last_updated = model.time;
+            Event::SetNational(_, Err(e)) => {
+                model.national.error = Some(e.clone());
+                model.national.scope.intensity_error = Some(e);
+                model.national.failures.record(model.time);
+                finish_national(Operation::NationalIntensity, model);
+                render(model, caps);
+            }
+            Event::SetNationalMix(_, Err(e)) => {
+                model.national.error = Some(e.clone());
+                model.national.scope.mix_error = Some(e);
+                model.national.failures.record(model.time);
+                finish_national(Operation::NationalMix, model);
+                render(model, caps);
+            }
+            Event::SetNationalPast(_, Ok(response)) => {
+                model.national.past = response.data;
+                model.national.finish(Operation::Past);
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetRegionalPast(place, _, Ok(response)) => {
+                if let Some(local) = model.local_mut(&place) {
+                    local.past = response.data.data;
+                    local.finish(Operation::Past);
+                }
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetRegionPast(_, Ok(response)) => {
+                model.regions.past = response.data.data;
+                model.regions.finish(Operation::Past);
+                save(model, caps);
+                render(model, caps);
+            }
+            // the past is only there for comparison, so we can do without it
+            Event::SetNationalPast(_, Err(_)) => {
+                model.national.finish(Operation::Past);
+                render(model, caps);
+            }
+            Event::SetRegionalPast(place, _, Err(_)) => {
+                if let Some(local) = model.local_mut(&place) {
+                    local.finish(Operation::Past);
+                }
+                render(model, caps);
+            }
+            Event::SetRegionPast(_, Err(_)) => {
+                model.regions.finish(Operation::Past);
+                render(model, caps);
+            }
+            Event::SetFactors(Ok(response)) => {
+                model.pending.retain(|op| *op != Operation::Factors);
+                if let Some(factors) = response.data.into_iter().next() {
+                    model.factors = Some(factors);
+                    save(model, caps);
+                    render(model, caps);
+                }
+            }
+            Event::SetFactors(Err(_)) => {
+                // keep using the built-in factors, and try again next time
+                model.pending.retain(|op| *op != Operation::Factors);
+            }
+            Event::SetRegions(Ok(response)) => {
+                model.regions.scope.regions = response.regions();
+                model.regions.scope.last_updated = model.time;
+                model.regions.scope.regions_error = None;
+                finish_regions(Operation::Regions, model);
+
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetRegion(_, Ok(response)) => {
+                model.regions.periods = response.data.data;
+                model.regions.last_updated = model.time;
+                model.regions.scope.region_error = None;
+                finish_regions(Operation::Region, model);
+
+                notify(model, caps);
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetSite(index, _, Ok(response)) => {
+                if let Some(forecast) = model.sites.scope.forecasts.get_mut(index) {
+                    forecast.region = Some(response.data.shortname);
+                    forecast.periods = response.data.data;
+                }
+                finish_sites(model);
+
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetSite(_, _, Err(e)) => {
+                let sites = &mut model.sites;
+                sites.error = Some(e.clone());
+                sites.scope.site_error.get_or_insert(e);
+                sites.failures.record(model.time);
+                finish_sites(model);
+                render(model, caps);
+            }
+            Event::SetHistory(range, _, Ok(response)) => {
+                let bounds = range.bounds(model.time);
+                history::merge(&mut model.history.periods, response.data, bounds);
+                finish_history(model);
 
-                caps.render.render();
+                save(model, caps);
+                render(model, caps);
+            }
+            Event::SetHistory(_, _, Err(e)) => {
+                let history = &mut model.history;
+                history.error = Some(e.clone());
+                history.scope.chunk_error.get_or_insert(e);
+                history.failures.record(model.time);
+                finish_history(model);
+                render(model, caps);
+            }
+            Event::SetRegions(Err(e)) => {
+                model.regions.scope.regions_error = Some(e);
+                model.regions.failures.record(model.time);
+                finish_regions(Operation::Regions, model);
+                render(model, caps);
+            }
+            Event::SetRegion(_, Err(e)) => {
+                model.regions.scope.region_error = Some(e);
+                model.regions.failures.record(model.time);
+                finish_regions(Operation::Region, model);
+                render(model, caps);
             }
-            Event::SetNationalMix(Err(_)) => {}
         };
+
+        finish_background(model, caps);
     }
 
     fn view(&self, model: &Self::Model) -> Self::ViewModel {
@@ -160,22 +849,491 @@ impl crux_core::App for App {
     }
 }
 
+/// Asks for the time, which then fetches the data for `scope` (see
+/// `Event::Refresh`)
+fn refresh(scope: Mode, force: bool, model: &mut Model, caps: &Capabilities) {
+    // always ask for the time, so that a forced refresh isn't lost if we're
+    // already waiting for the time for a normal one
+    match scope {
+        Mode::National => model.national.start(Operation::Time),
+        Mode::Local => model.selected_mut().start(Operation::Time),
+        Mode::Regions => model.regions.start(Operation::Time),
+        Mode::Compare => {
+            model.national.start(Operation::Time);
+            model.selected_mut().start(Operation::Time);
+        }
+    }
+    caps.time
+        .get(move |time| Event::SetTimeRefresh(scope, force, time));
+}
+
+fn set_history_range(range: HistoryRange, model: &mut Model) {
+    if model.history.scope.range.as_ref() != Some(&range) {
+        model.history.scope.range = Some(range);
+        let generation = model.next_generation();
+        model.history.clear(generation);
+    }
+}
+
+/// Whether a response is for data that has since been cleared (or removed),
+/// in which case it's too late to be of any use
+fn is_superseded(event: &Event, model: &Model) -> bool {
+    let (generation, current) = match event {
+        Event::SetLocation(generation, _) | Event::SetPostcode(generation, _) => {
+            (*generation, model.local.generation)
+        }
+        Event::SetLookup(place, generation, _)
+        | Event::SetRegional(place, generation, _)
+        | Event::SetRegionalPast(place, generation, _) => match model.local(place) {
+            Some(local) => (*generation, local.generation),
+            None => return true,
+        },
+        Event::SetNational(generation, _)
+        | Event::SetNationalMix(generation, _)
+        | Event::SetNationalPast(generation, _) => (*generation, model.national.generation),
+        Event::SetRegion(generation, _) | Event::SetRegionPast(generation, _) => {
+            (*generation, model.regions.generation)
+        }
+        Event::SetHistory(_, generation, _) => (*generation, model.history.generation),
+        Event::SetSite(_, generation, _) => (*generation, model.sites.generation),
+        _ => return false,
+    };
+    generation != current
+}
+
+/// The national intensity and mix are fetched separately, so the data's only
+/// up to date, and any error and failures cleared, once both have arrived
+/// without one
+fn finish_national(operation: Operation, model: &mut Model) {
+    let national = &mut model.national;
+    national.finish(operation);
+    if national.is_pending(Operation::NationalIntensity)
+        || national.is_pending(Operation::NationalMix)
+    {
+        return;
+    }
+
+    let scope = &national.scope;
+    national.error = scope
+        .intensity_error
+        .clone()
+        .or_else(|| scope.mix_error.clone());
+    if national.error.is_none() {
+        national.last_updated = model.time;
+        national.failures.reset();
+    }
+}
+
+fn get_regional(place: Place, location: Location, model: &mut Model, caps: &Capabilities) {
+    let url = regional::url(&model.time, &location.outcode, model.horizon);
+    let past_url = regional::past_url(&model.time, &location.outcode);
+    let Some(local) = model.local_mut(&place) else {
+        return;
+    };
+    let generation = local.generation;
+    caps.http.get(url).send({
+        let place = place.clone();
+        move |response| Event::SetRegional(place, generation, error::decode(response))
+    });
+    caps.http.get(past_url).send({
+        let place = place.clone();
+        move |response| Event::SetRegionalPast(place, generation, error::decode(response))
+    });
+    local.scope.location = Some(location);
+    local.start(Operation::Regional);
+    local.start(Operation::Past);
+
+    render(model, caps);
+}
+
+/// Asks the shell for the time, which then fetches the `data` if it's due,
+/// unless we're already waiting for the time for it
+fn get_time<T, F>(
+    model: &mut Model,
+    data: fn(&mut Model) -> &mut Data<T>,
+    caps: &Capabilities,
+    callback: F,
+) where
+    T: DataSet + Serialize,
+    F: Fn(TimeResponse) -> Event + Send + Sync + 'static,
+{
+    let data = data(model);
+    if data.is_pending(Operation::Time) {
+        render(model, caps);
+        return;
+    }
+    data.start(Operation::Time);
+    caps.time.get(callback);
+}
+
+fn set_time(TimeResponse(iso_time): TimeResponse, model: &mut Model) {
+    model.time = DateTime::parse_from_rfc3339(&iso_time)
+        .unwrap()
+        .with_timezone(&Utc);
+}
+
+/// Finds the location of the selected place, which then fetches its forecast
+fn refresh_local(model: &mut Model, caps: &Capabilities, force: bool) {
+    let place = model.place.clone();
+    let local = model.selected();
+    let due = model
+        .refresh
+        .should_refresh(local.last_updated, &local.failures, model.time, force);
+    if !due || local.is_fetching() {
+        render(model, caps);
+        return;
+    }
+
+    let generation = local.generation;
+    let operation = match &local.scope.search {
+        Some(search) => {
+            caps.http
+                .get(search.url())
+                .send(move |response| Event::SetLookup(place, generation, error::decode(response)));
+            Operation::Lookup
+        }
+        None => {
+            caps.location
+                .get(move |response| Event::SetLocation(generation, response));
+            Operation::Location
+        }
+    };
+    model.selected_mut().start(operation);
+    get_factors(model, caps);
+}
+
+fn refresh_national(model: &mut Model, caps: &Capabilities, force: bool) {
+    let national = &model.national;
+    let due =
+        model
+            .refresh
+            .should_refresh(national.last_updated, &national.failures, model.time, force);
+    if !due || national.is_fetching() {
+        render(model, caps);
+        return;
+    }
+
+    let generation = national.generation;
+    caps.http
+        .get(national_intensity::url(&model.time, model.horizon))
+        .send(move |response| Event::SetNational(generation, error::decode(response)));
+    caps.http
+        .get(national_mix::url(&model.time, model.horizon))
+        .send(move |response| Event::SetNationalMix(generation, error::decode(response)));
+    caps.http
+        .get(national_intensity::past_url(&model.time))
+        .send(move |response| Event::SetNationalPast(generation, error::decode(response)));
+    model.national.start(Operation::NationalIntensity);
+    model.national.start(Operation::NationalMix);
+    model.national.start(Operation::Past);
+    get_factors(model, caps);
+}
+
+/// Fetches the list of regions and, if one is selected, its forecast
+fn refresh_regions(model: &mut Model, caps: &Capabilities, force: bool) {
+    let regions = &model.regions;
+    if regions.is_fetching() {
+        render(model, caps);
+        return;
+    }
+
+    let mut operations = vec![];
+    if model.refresh.should_refresh(
+        regions.scope.last_updated,
+        &regions.failures,
+        model.time,
+        force,
+    ) {
+        caps.http
+            .get(regional::all_url())
+            .send(|response| Event::SetRegions(error::decode(response)));
+        operations.push(Operation::Regions);
+    }
+    if let Some(region_id) = regions.scope.selected {
+        if model
+            .refresh
+            .should_refresh(regions.last_updated, &regions.failures, model.time, force)
+        {
+            let generation = regions.generation;
+            caps.http
+                .get(regional::region_url(&model.time, region_id, model.horizon))
+                .send(move |response| Event::SetRegion(generation, error::decode(response)));
+            caps.http
+                .get(regional::region_past_url(&model.time, region_id))
+                .send(move |response| Event::SetRegionPast(generation, error::decode(response)));
+            operations.push(Operation::Region);
+            operations.push(Operation::Past);
+        }
+    }
+
+    if operations.is_empty() {
+        render(model, caps);
+    }
+    for operation in operations {
+        model.regions.start(operation);
+    }
+}
+
+fn refresh_history(model: &mut Model, caps: &Capabilities, force: bool) {
+    let history = &model.history;
+    let due =
+        model
+            .refresh
+            .should_refresh(history.last_updated, &history.failures, model.time, force);
+    let Some(range) = history.scope.range.clone() else {
+        render(model, caps);
+        return;
+    };
+    if !due || history.is_fetching() {
+        render(model, caps);
+        return;
+    }
+
+    let generation = history.generation;
+    model.history.scope.chunk_error = None;
+    for url in range.urls(model.time) {
+        model.history.start(Operation::History);
+        let range = range.clone();
+        caps.http
+            .get(url)
+            .send(move |response| Event::SetHistory(range, generation, error::decode(response)));
+    }
+}
+
+/// Finishes fetching the list of regions or the selected region's forecast.
+/// Either one failing is reported (and backed off from) until it succeeds,
+/// whatever happens to the other.
+fn finish_regions(operation: Operation, model: &mut Model) {
+    let regions = &mut model.regions;
+    regions.finish(operation);
+
+    let scope = &regions.scope;
+    regions.error = scope
+        .regions_error
+        .clone()
+        .or_else(|| scope.region_error.clone());
+    if regions.error.is_none() {
+        regions.failures.reset();
+    }
+}
+
+/// Finishes one of the chunks of history and, once they've all arrived,
+/// sets the error from the first one that failed, if any did
+fn finish_history(model: &mut Model) {
+    let history = &mut model.history;
+    history.finish(Operation::History);
+    if history.is_pending(Operation::History) {
+        return;
+    }
+
+    history.error = history.scope.chunk_error.clone();
+    if history.error.is_none() {
+        history.last_updated = model.time;
+        history.failures.reset();
+    }
+}
+
+/// Finishes one of the sites' forecasts and, once they've all arrived, sets
+/// the error from the first one that failed, if any did
+fn finish_sites(model: &mut Model) {
+    let sites = &mut model.sites;
+    sites.finish(Operation::Site);
+    if sites.is_pending(Operation::Site) {
+        return;
+    }
+
+    sites.error = sites.scope.site_error.clone();
+    if sites.error.is_none() {
+        sites.last_updated = model.time;
+        sites.failures.reset();
+    }
+}
+
+/// Fetches the forecast for each of the sites being compared
+fn refresh_sites(model: &mut Model, caps: &Capabilities, force: bool) {
+    let sites = &model.sites;
+    let due = model
+        .refresh
+        .should_refresh(sites.last_updated, &sites.failures, model.time, force);
+    if !due || sites.is_fetching() || sites.scope.forecasts.is_empty() {
+        render(model, caps);
+        return;
+    }
+
+    let generation = sites.generation;
+    model.sites.scope.site_error = None;
+    let sites = &model.sites;
+    for (index, forecast) in sites.scope.forecasts.iter().enumerate() {
+        caps.http
+            .get(forecast.site.url(&model.time, model.horizon))
+            .send(move |response| Event::SetSite(index, generation, error::decode(response)));
+    }
+    for _ in 0..model.sites.scope.forecasts.len() {
+        model.sites.start(Operation::Site);
+    }
+}
+
+fn set_local_error(place: &Place, error: Error, operation: Operation, model: &mut Model) {
+    let time = model.time;
+    if let Some(local) = model.local_mut(place) {
+        local.error = Some(error);
+        local.failures.record(time);
+        local.finish(operation);
+    }
+}
+
+/// Asks the shell to render, unless we're refreshing in the background and
+/// the shell hasn't sent anything else since
+fn render(model: &Model, caps: &Capabilities) {
+    if model
+        .background
+        .as_ref()
+        .map_or(true, |background| background.foreground)
+    {
+        caps.render.render();
+    }
+}
+
+/// Tells the shell that a background refresh has finished, once we're no
+/// longer waiting for any of its data
+fn finish_background(model: &mut Model, caps: &Capabilities) {
+    let Some(background) = &model.background else {
+        return;
+    };
+    let local = model.selected();
+    if model.national.is_loading() || local.is_fetching() {
+        return;
+    }
+
+    let policy = &model.refresh;
+    let mut next_refresh =
+        policy.next_refresh(model.national.last_updated, &model.national.failures);
+    if local.has_place() {
+        next_refresh = next_refresh.min(policy.next_refresh(local.last_updated, &local.failures));
+    }
+    caps.background.complete(RefreshComplete {
+        changed: background.national != model.national.periods || background.local != local.periods,
+        next_refresh: next_refresh.to_rfc3339(),
+    });
+    model.background = None;
+}
+
+/// Stores the model so that it can be restored when the app next starts
+fn save(model: &Model, caps: &Capabilities) {
+    caps.key_value.write(storage::KEY, storage::save(model));
+}
+
+/// Schedules a notification for each upcoming period that meets one of the
+/// thresholds, unless we've already done so, and cancels any for periods
+/// that no longer meet them now that the forecast has changed
+fn notify(model: &mut Model, caps: &Capabilities) {
+    let now = model.time;
+    model.notified.retain(|notified| notified.from > now);
+
+    let mut due = vec![];
+    let mut stale = vec![];
+    for threshold in &model.thresholds {
+        let periods = match threshold.scope {
+            Mode::National => &model.national.periods,
+            Mode::Local | Mode::Compare => &model.selected().periods,
+            Mode::Regions => &model.regions.periods,
+        };
+        // without a forecast, we can't tell whether anything has changed
+        if periods.is_empty() {
+            continue;
+        }
+
+        let crossings = threshold.crossings(periods, now);
+        stale.extend(
+            model
+                .notified
+                .iter()
+                .filter(|notified| {
+                    notified.threshold == threshold.name && !crossings.contains(&notified.from)
+                })
+                .cloned(),
+        );
+        for from in crossings {
+            let notified = Notified {
+                threshold: threshold.name.clone(),
+                from,
+            };
+            if !model.notified.contains(&notified) {
+                due.push((threshold, notified));
+            }
+        }
+    }
+
+    for notified in &stale {
+        caps.notify.cancel(&notification_id(notified));
+    }
+    model.notified.retain(|notified| !stale.contains(notified));
+
+    let mut notified = vec![];
+    for (threshold, period) in due {
+        caps.notify.schedule(Notification {
+            id: notification_id(&period),
+            title: threshold.name.clone(),
+            body: threshold.message(period.from, model.zone),
+            at: threshold.notify_at(period.from).to_rfc3339(),
+        });
+        notified.push(period);
+    }
+    model.notified.extend(notified);
+}
+
+/// Cancels the notifications we've scheduled for a threshold, which will
+/// be scheduled again if it's replaced
+fn cancel_notifications(name: &str, model: &mut Model, caps: &Capabilities) {
+    model.notified.retain(|notified| {
+        if notified.threshold == name {
+            caps.notify.cancel(&notification_id(notified));
+        }
+        notified.threshold != name
+    });
+}
+
+fn notification_id(notified: &Notified) -> String {
+    format!("{}@{}", notified.threshold, notified.from.to_rfc3339())
+}
+
+/// Fetches the emission factors, unless we already have them or have
+/// already asked for them. Until they arrive (or if the request fails) the
+/// built-in factors are used.
+fn get_factors(model: &mut Model, caps: &Capabilities) {
+    if model.factors.is_none() && !model.pending.contains(&Operation::Factors) {
+        caps.http
+            .get(factors::url())
+            .send(|response| Event::SetFactors(error::decode(response)));
+        model.pending.push(Operation::Factors);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        capabilities,
+        capabilities::{
+            self,
+            key_value::{KeyValueRequest, KeyValueResponse},
+            notification::NotificationRequest,
+        },
         model::{
-            location::Location, national_intensity::NationalResponse,
-            national_mix::NationalMixResponse, postcode::PostcodeResponse,
-            regional::RegionalResponse,
+            factors::Factors,
+            freshness::Freshness,
+            intensity::{Intensity, IntensityIndex, Period},
+            location::Location,
+            national_intensity::NationalResponse,
+            national_mix::NationalMixResponse,
+            postcode::PostcodeResponse,
+            regional::{RegionalResponse, RegionsResponse},
+            thresholds::Condition,
         },
     };
+    use assert_matches::assert_matches;
+    use chrono::Duration;
     use crux_core::{assert_effect, testing::AppTester};
-    use crux_http::{
-        protocol::{HttpRequest, HttpResponse},
-        testing::ResponseBuilder,
-    };
+    use crux_http::protocol::{HttpRequest, HttpResponse};
 
     #[test]
     fn local_happy_path() {
@@ -189,7 +1347,7 @@ mod tests {
 
         // resolve the time request with a simulated time response
         let mut request = requests.next().unwrap();
-        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
         let update = app.resolve(&mut request, response.clone()).unwrap();
 
         // check this raises the correct set time event
@@ -202,7 +1360,7 @@ mod tests {
         let update = app.update(set_time_event, &mut model);
         assert_eq!(
             model.time,
-            DateTime::parse_from_rfc3339("2023-07-06T20:30:00Z")
+            DateTime::parse_from_rfc3339("2023-07-04T23:45:00Z")
                 .unwrap()
                 .with_timezone(&Utc)
         );
@@ -219,7 +1377,7 @@ mod tests {
         let update = app.resolve(&mut request, response.clone()).unwrap();
 
         // check the this raises a SetLocation event
-        let set_location_event = Event::SetLocation(response.clone());
+        let set_location_event = Event::SetLocation(0, response.clone());
         let actual = &update.events;
         let expected = &vec![set_location_event.clone()];
         assert_eq!(actual, expected);
@@ -248,10 +1406,7 @@ mod tests {
         let update = app.resolve(&mut request, response).unwrap();
 
         // check the postcode response raises a SetPostcode event
-        let set_postcode_event = Event::SetPostcode(Ok(ResponseBuilder::ok()
-            .body(simulated_response)
-            .build()
-            .clone()));
+        let set_postcode_event = Event::SetPostcode(0, Ok(simulated_response));
         let actual = &update.events;
         let expected = &vec![set_postcode_event.clone()];
         assert_eq!(actual, expected);
@@ -274,14 +1429,21 @@ mod tests {
             }
         );
 
-        // get the first http request and check there are no more
+        // get the regional request, followed by one for the past 24 hours
         let mut request = requests.next().unwrap();
+        let past = requests.next().unwrap();
         assert!(requests.next().is_none());
 
-        // check the regional request has the expected url
+        // check the regional requests have the expected urls
         let actual = &request.operation;
         let expected = &HttpRequest::get(
-            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/fw24h/postcode/KT1",
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/fw24h/postcode/KT1",
+        )
+        .build();
+        assert_eq!(actual, expected);
+        let actual = &past.operation;
+        let expected = &HttpRequest::get(
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/pt24h/postcode/KT1",
         )
         .build();
         assert_eq!(actual, expected);
@@ -293,10 +1455,7 @@ mod tests {
         let update = app.resolve(&mut request, response).unwrap();
 
         // check the regional response raises a SetRegional event
-        let set_regional_event = Event::SetRegional(Ok(ResponseBuilder::ok()
-            .body(simulated_response)
-            .build()
-            .clone()));
+        let set_regional_event = Event::SetRegional(Place::Current, 0, Ok(simulated_response));
         let actual = &update.events;
         let expected = &vec![set_regional_event.clone()];
         assert_eq!(actual, expected);
@@ -315,6 +1474,7 @@ mod tests {
               longitude: -0.298333
             outcode: KT1
             admin_district: Kingston upon Thames
+          search: ~
         periods:
           - from: "2023-07-04T23:30:00Z"
             to: "2023-07-05T00:00:00Z"
@@ -340,37 +1500,145 @@ mod tests {
                 perc: 16.1
               - fuel: nuclear
                 perc: 0
-        last_updated: "2023-07-06T20:30:00Z"
+        past: []
+        last_updated: "2023-07-04T23:45:00Z"
+        failures:
+          count: 0
+          last: "1970-01-01T00:00:00Z"
         "###);
 
         // check that the view renders as expected
         insta::assert_yaml_snapshot!(app.view(&model), @r###"
         ---
         mode: Local
+        horizon: Hours24
+        zone: UnitedKingdom
+        now_label: "00:45 BST"
+        days:
+          - date: 2023-07-05
+            label: Wed 5 Jul
+            from: "2023-07-04T23:00:00+00:00"
+            to: "2023-07-05T23:00:00+00:00"
+            half_hours: 48
         national_name: UK
         national_intensity: []
         national_mix: []
+        national_mix_categories: []
+        national_mix_average: ~
+        national_error: ~
+        national_accuracy: ~
+        national_freshness: NeverLoaded
+        national_as_of: ~
+        national_current: ~
+        national_loading: false
         local_name: "Kingston upon Thames, KT1"
         local_intensity:
           - date: "2023-07-04T23:30:00+00:00"
             forecast: 121
             actual: ~
+            derived: 0
+            mix_quality: Inconsistent
+            uncertain: false
+            label: "00:30"
+            starts_day: false
+            now: true
           - date: "2023-07-05T00:00:00+00:00"
             forecast: 116
             actual: ~
+            derived: 63
+            mix_quality: Inconsistent
+            uncertain: false
+            label: "01:00"
+            starts_day: false
+            now: false
         local_mix:
           - date: "2023-07-05T00:00:00+00:00"
             fuel: Gas
+            label: Gas
             perc: 16.1
+            grams: 63.434001502990725
           - date: "2023-07-05T00:00:00+00:00"
             fuel: Nuclear
+            label: Nuclear
             perc: 0
+            grams: 0
           - date: "2023-07-04T23:30:00+00:00"
             fuel: Wind
+            label: Wind
             perc: 16.5
+            grams: 0
           - date: "2023-07-04T23:30:00+00:00"
             fuel: Solar
+            label: Solar
             perc: 0
+            grams: 0
+        local_mix_categories:
+          - date: "2023-07-04T23:30:00+00:00"
+            categories:
+              renewable: 16.5
+              low_carbon: 16.5
+              fossil: 0
+              imports: 0
+              other: 0
+          - date: "2023-07-05T00:00:00+00:00"
+            categories:
+              renewable: 0
+              low_carbon: 0
+              fossil: 16.100000381469727
+              imports: 0
+              other: 0
+        local_mix_average:
+          renewable: 8.25
+          low_carbon: 8.25
+          fossil: 8.050000190734863
+          imports: 0
+          other: 0
+        local_error: ~
+        local_accuracy: ~
+        local_freshness: Fresh
+        local_as_of: "2023-07-04T23:45:00+00:00"
+        local_current:
+          date: "2023-07-04T23:30:00+00:00"
+          forecast: 121
+          actual: ~
+          derived: 0
+          mix_quality: Inconsistent
+          uncertain: false
+          label: "00:30"
+          starts_day: false
+          now: true
+        local_loading: true
+        saved_locations: []
+        regions: []
+        region_name: Regions
+        region_intensity: []
+        region_mix: []
+        region_mix_categories: []
+        region_mix_average: ~
+        region_error: ~
+        region_accuracy: ~
+        region_freshness: NeverLoaded
+        region_as_of: ~
+        region_current: ~
+        region_loading: false
+        history_name: History
+        history_intensity: []
+        history_error: ~
+        history_accuracy: ~
+        history_loading: false
+        greenest_window: ~
+        job_error: ~
+        footprint: ~
+        footprint_error: ~
+        thresholds: []
+        threshold_error: ~
+        daily_summary: ~
+        comparison: []
+        comparison_current: ~
+        comparison_headline: ~
+        site_comparison: ~
+        sites_error: ~
+        sites_loading: false
         "###);
     }
 
@@ -386,7 +1654,7 @@ mod tests {
 
         // resolve the time request with a simulated time response
         let mut request = requests.next().unwrap();
-        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
         let update = app.resolve(&mut request, response.clone()).unwrap();
 
         // check this raises the correct set time event
@@ -399,12 +1667,13 @@ mod tests {
         let update = app.update(set_time_event, &mut model);
         assert_eq!(
             model.time,
-            DateTime::parse_from_rfc3339("2023-07-06T20:30:00Z")
+            DateTime::parse_from_rfc3339("2023-07-04T23:45:00Z")
                 .unwrap()
                 .with_timezone(&Utc)
         );
 
-        // we should get 2 http requests, one for intensity and one for generation mix
+        // we should get 4 http requests, for intensity, generation mix, the past
+        // 24 hours and emission factors
         let requests = &mut update.into_effects().filter_map(Effect::into_http);
 
         // get the first http request
@@ -413,7 +1682,7 @@ mod tests {
         // check the intensity request has the expected url
         let actual = &request.operation;
         let expected = &HttpRequest::get(
-            "https://api.carbonintensity.org.uk/intensity/2023-07-06T20:30Z/fw24h",
+            "https://api.carbonintensity.org.uk/intensity/2023-07-04T23:45Z/fw24h",
         )
         .build();
         assert_eq!(actual, expected);
@@ -425,10 +1694,7 @@ mod tests {
         let update = app.resolve(&mut request, response).unwrap();
 
         // check the intensity response raises a SetNational event
-        let set_national_event = Event::SetNational(Ok(ResponseBuilder::ok()
-            .body(simulated_response)
-            .build()
-            .clone()));
+        let set_national_event = Event::SetNational(0, Ok(simulated_response));
         let actual = &update.events;
         let expected = &vec![set_national_event.clone()];
         assert_eq!(actual, expected);
@@ -457,7 +1723,11 @@ mod tests {
               actual: 122
               index: moderate
             generationmix: ~
-        last_updated: "2023-07-06T20:30:00Z"
+        past: []
+        last_updated: "1970-01-01T00:00:00Z"
+        failures:
+          count: 0
+          last: "1970-01-01T00:00:00Z"
         "###);
 
         // get the second http request
@@ -466,7 +1736,7 @@ mod tests {
         // check the generation mix request has the expected url
         let actual = &request.operation;
         let expected = &HttpRequest::get(
-            "https://api.carbonintensity.org.uk/generation/2023-07-06T20:30Z/2023-07-07T20:30Z",
+            "https://api.carbonintensity.org.uk/generation/2023-07-04T23:45Z/2023-07-05T23:45Z",
         )
         .build();
         assert_eq!(actual, expected);
@@ -478,10 +1748,7 @@ mod tests {
         let update = app.resolve(&mut request, response).unwrap();
 
         // check the intensity response raises a SetNational event
-        let set_national_mix_event = Event::SetNationalMix(Ok(ResponseBuilder::ok()
-            .body(simulated_response)
-            .build()
-            .clone()));
+        let set_national_mix_event = Event::SetNationalMix(0, Ok(simulated_response));
         let actual = &update.events;
         let expected = &vec![set_national_mix_event.clone()];
         assert_eq!(actual, expected);
@@ -520,45 +1787,200 @@ mod tests {
               forecast: 142
               actual: 129
               index: moderate
-            generationmix: ~
+            generationmix:
+              - fuel: solar
+                perc: 0
+              - fuel: wind
+                perc: 21.3
+              - fuel: other
+                perc: 0
           - from: "2023-07-05T00:00:00Z"
             to: "2023-07-05T00:30:00Z"
             intensity:
               forecast: 136
               actual: 122
               index: moderate
-            generationmix: ~
-        last_updated: "2023-07-06T20:30:00Z"
+            generationmix:
+              - fuel: gas
+                perc: 41.1
+              - fuel: nuclear
+                perc: 24.3
+        past: []
+        last_updated: "2023-07-04T23:45:00Z"
+        failures:
+          count: 0
+          last: "1970-01-01T00:00:00Z"
         "###);
 
-        // check the view renders as expected
-        insta::assert_yaml_snapshot!(app.view(&model), @r###"
-        ---
+        // get the third http request, for the past 24 hours
+        let request = requests.next().unwrap();
+        let actual = &request.operation;
+        let expected = &HttpRequest::get(
+            "https://api.carbonintensity.org.uk/intensity/2023-07-04T23:45Z/pt24h",
+        )
+        .build();
+        assert_eq!(actual, expected);
+
+        // get the fourth http request, for the emission factors
+        let mut request = requests.next().unwrap();
+        let actual = &request.operation;
+        let expected =
+            &HttpRequest::get("https://api.carbonintensity.org.uk/intensity/factors").build();
+        assert_eq!(actual, expected);
+
+        // resolve a simulated factors response and check the model uses it
+        let simulated_response: factors::Root =
+            serde_json::from_str(include_str!("./fixtures/factors.json")).unwrap();
+        let response = HttpResponse::ok().json(&simulated_response).build();
+        let update = app.resolve(&mut request, response).unwrap();
+        for event in update.events {
+            let update = app.update(event, &mut model);
+            assert_effect!(update, Effect::Render(_));
+        }
+        assert_eq!(model.factors, Some(Factors::new()));
+
+        // check the view renders as expected
+        insta::assert_yaml_snapshot!(app.view(&model), @r###"
+        ---
         mode: National
+        horizon: Hours24
+        zone: UnitedKingdom
+        now_label: "00:45 BST"
+        days:
+          - date: 2023-07-05
+            label: Wed 5 Jul
+            from: "2023-07-04T23:00:00+00:00"
+            to: "2023-07-05T23:00:00+00:00"
+            half_hours: 48
         national_name: UK
         national_intensity:
           - date: "2023-07-04T23:30:00+00:00"
             forecast: 142
             actual: 129
+            derived: 0
+            mix_quality: Inconsistent
+            uncertain: false
+            label: "00:30"
+            starts_day: false
+            now: true
           - date: "2023-07-05T00:00:00+00:00"
             forecast: 136
             actual: 122
+            derived: 162
+            mix_quality: Inconsistent
+            uncertain: false
+            label: "01:00"
+            starts_day: false
+            now: false
         national_mix:
           - date: "2023-07-05T00:00:00+00:00"
             fuel: Gas
+            label: Gas
             perc: 41.1
+            grams: 161.93399398803712
           - date: "2023-07-05T00:00:00+00:00"
             fuel: Nuclear
+            label: Nuclear
             perc: 24.3
+            grams: 0
           - date: "2023-07-04T23:30:00+00:00"
             fuel: Wind
+            label: Wind
             perc: 21.3
+            grams: 0
           - date: "2023-07-04T23:30:00+00:00"
             fuel: Solar
+            label: Solar
             perc: 0
+            grams: 0
+        national_mix_categories:
+          - date: "2023-07-04T23:30:00+00:00"
+            categories:
+              renewable: 21.299999237060547
+              low_carbon: 21.299999237060547
+              fossil: 0
+              imports: 0
+              other: 0
+          - date: "2023-07-05T00:00:00+00:00"
+            categories:
+              renewable: 0
+              low_carbon: 24.299999237060547
+              fossil: 41.099998474121094
+              imports: 0
+              other: 0
+        national_mix_average:
+          renewable: 10.649999618530273
+          low_carbon: 22.799999237060547
+          fossil: 20.549999237060547
+          imports: 0
+          other: 0
+        national_error: ~
+        national_accuracy:
+          mean_absolute_error: 13.5
+          bias: 13.5
+          mean_absolute_percentage_error: 10.776464607955267
+          periods:
+            - date: "2023-07-04T23:30:00+00:00"
+              error: 13
+              percentage_error: 10.077519379844961
+            - date: "2023-07-05T00:00:00+00:00"
+              error: 14
+              percentage_error: 11.475409836065573
+        national_freshness: Fresh
+        national_as_of: "2023-07-04T23:45:00+00:00"
+        national_current:
+          date: "2023-07-04T23:30:00+00:00"
+          forecast: 142
+          actual: 129
+          derived: 0
+          mix_quality: Inconsistent
+          uncertain: false
+          label: "00:30"
+          starts_day: false
+          now: true
+        national_loading: true
         local_name: Local
         local_intensity: []
         local_mix: []
+        local_mix_categories: []
+        local_mix_average: ~
+        local_error: ~
+        local_accuracy: ~
+        local_freshness: NeverLoaded
+        local_as_of: ~
+        local_current: ~
+        local_loading: false
+        saved_locations: []
+        regions: []
+        region_name: Regions
+        region_intensity: []
+        region_mix: []
+        region_mix_categories: []
+        region_mix_average: ~
+        region_error: ~
+        region_accuracy: ~
+        region_freshness: NeverLoaded
+        region_as_of: ~
+        region_current: ~
+        region_loading: false
+        history_name: History
+        history_intensity: []
+        history_error: ~
+        history_accuracy: ~
+        history_loading: false
+        greenest_window: ~
+        job_error: ~
+        footprint: ~
+        footprint_error: ~
+        thresholds: []
+        threshold_error: ~
+        daily_summary: ~
+        comparison: []
+        comparison_current: ~
+        comparison_headline: ~
+        site_comparison: ~
+        sites_error: ~
+        sites_loading: false
         "###);
     }
 
@@ -572,11 +1994,11 @@ mod tests {
 
         // request "local" data and get a time request
         let update = app.update(Event::GetLocal, &mut model);
-        let mut request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
 
         // resolve the time request with a simulated time response
         let response = TimeResponse("2023-07-06T20:59:00Z".to_string());
-        let update = app.resolve(&mut request, response).unwrap();
+        let update = app.resolve(request, response).unwrap();
 
         // update the app and check we only get a render effect
         for event in update.events {
@@ -596,11 +2018,11 @@ mod tests {
 
         // request "national" data and get a time request
         let update = app.update(Event::GetNational, &mut model);
-        let mut request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
 
         // resolve the time request with a simulated time response
         let response = TimeResponse("2023-07-06T21:01:00Z".to_string());
-        let update = app.resolve(&mut request, response).unwrap();
+        let update = app.resolve(request, response).unwrap();
 
         // update the app and check the resulting request has the expected url
         let update = app.update(update.events[0].clone(), &mut model);
@@ -612,4 +2034,1830 @@ mod tests {
         .build();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn schedule_job_in_greenest_window() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        model.national.periods = national.data;
+        model.time = DateTime::parse_from_rfc3339("2023-07-04T23:40:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let update = app.update(
+            Event::ScheduleJob {
+                duration_minutes: 30,
+                deadline: "2023-07-05T00:30:00Z".to_string(),
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+
+        insta::assert_yaml_snapshot!(app.view(&model).greenest_window, @r###"
+        ---
+        from: "2023-07-05T00:00:00+00:00"
+        to: "2023-07-05T00:30:00+00:00"
+        forecast: 136
+        run_now_forecast: 142
+        saving: 6
+        saving_percent: 4.225352112676056
+        uncertain: false
+        "###);
+
+        // a deadline that can't be parsed is reported rather than ignored
+        let update = app.update(
+            Event::ScheduleJob {
+                duration_minutes: 30,
+                deadline: "tomorrow".to_string(),
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        let view = app.view(&model);
+        assert!(view.greenest_window.is_none());
+        assert_eq!(
+            view.job_error,
+            Some("\"tomorrow\" is not a valid date".to_string())
+        );
+
+        // as is a duration that doesn't fit in the forecast
+        app.update(
+            Event::ScheduleJob {
+                duration_minutes: i64::MAX,
+                deadline: "2023-07-05T00:30:00Z".to_string(),
+            },
+            &mut model,
+        );
+        let view = app.view(&model);
+        assert!(view.greenest_window.is_none());
+        assert_eq!(
+            view.job_error,
+            Some(format!("Can't schedule a job lasting {} minutes", i64::MAX))
+        );
+
+        app.update(Event::ClearJob, &mut model);
+        assert!(app.view(&model).greenest_window.is_none());
+        assert!(app.view(&model).job_error.is_none());
+    }
+
+    #[test]
+    fn footprint_for_past_usage() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        model.history.periods = national.data;
+
+        let update = app.update(
+            Event::CalculateFootprint {
+                energy_kwh: 2.0,
+                from: "2023-07-04T23:45:00Z".to_string(),
+                to: "2023-07-05T00:15:00Z".to_string(),
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+
+        // the usage spans two periods, so uses half of each period's actual
+        insta::assert_yaml_snapshot!(app.view(&model).footprint, @r###"
+        ---
+        from: "2023-07-04T23:45:00+00:00"
+        to: "2023-07-05T00:15:00+00:00"
+        grams: 251
+        best:
+          from: "2023-07-05T00:00:00+00:00"
+          to: "2023-07-05T00:30:00+00:00"
+          grams: 244
+        worst:
+          from: "2023-07-04T23:30:00+00:00"
+          to: "2023-07-05T00:00:00+00:00"
+          grams: 258
+        "###);
+
+        // negative energy, or a time that can't be parsed, is reported
+        let update = app.update(
+            Event::CalculateFootprint {
+                energy_kwh: -2.0,
+                from: "2023-07-04T23:45:00Z".to_string(),
+                to: "2023-07-05T00:15:00Z".to_string(),
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        let view = app.view(&model);
+        assert!(view.footprint.is_none());
+        assert_eq!(
+            view.footprint_error,
+            Some("\"-2\" is not a valid amount of energy".to_string())
+        );
+        app.update(
+            Event::CalculateFootprint {
+                energy_kwh: 2.0,
+                from: "2023-07-04T23:45:00Z".to_string(),
+                to: "00:15".to_string(),
+            },
+            &mut model,
+        );
+        assert_eq!(
+            app.view(&model).footprint_error,
+            Some("\"00:15\" is not a valid date".to_string())
+        );
+
+        app.update(Event::ClearFootprint, &mut model);
+        assert!(app.view(&model).footprint.is_none());
+        assert!(app.view(&model).footprint_error.is_none());
+    }
+
+    #[test]
+    fn save_and_restore_model() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // check a successful fetch saves the model
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let update = app.update(Event::SetNational(0, Ok(national)), &mut model);
+        let request = update
+            .into_effects()
+            .find_map(Effect::into_key_value)
+            .unwrap();
+        let KeyValueRequest::Write(key, bytes) = request.operation else {
+            panic!("expected a write request");
+        };
+        assert_eq!(key, "model");
+
+        // start afresh and check the saved model is read back
+        let mut restored = Model::default();
+        let update = app.update(Event::Start, &mut restored);
+        let request = &mut update
+            .into_effects()
+            .find_map(Effect::into_key_value)
+            .unwrap();
+        assert_eq!(
+            request.operation,
+            KeyValueRequest::Read("model".to_string())
+        );
+
+        let update = app
+            .resolve(request, KeyValueResponse::Read(Some(bytes)))
+            .unwrap();
+        let update = app.update(update.events[0].clone(), &mut restored);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(restored, model);
+    }
+
+    #[test]
+    fn restore_while_waiting_for_data() {
+        let app = AppTester::<App, _>::default();
+        let stored = Model {
+            mode: Mode::Regions,
+            ..Default::default()
+        };
+        let mut model = Model::default();
+        let generation = model.next_generation();
+        model.national.clear(generation);
+        model.national.start(Operation::NationalIntensity);
+
+        app.update(Event::Load(Some(storage::save(&stored))), &mut model);
+        assert_eq!(model.mode, Mode::Regions);
+        assert!(model.national.is_pending(Operation::NationalIntensity));
+
+        // a response to a request from before the data was cleared is
+        // still ignored
+        let update = app.update(Event::SetNational(0, Err(Error::Status(503))), &mut model);
+        assert!(update.effects.is_empty());
+        assert_eq!(model.national.error, None);
+
+        app.update(Event::SetNational(1, Err(Error::Status(503))), &mut model);
+        assert_eq!(model.national.error, Some(Error::Status(503)));
+    }
+
+    #[test]
+    fn start_afresh_without_saved_model() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Load(Some(b"garbage".to_vec())), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(model, Model::default());
+    }
+
+    #[test]
+    fn national_server_error() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // request "national" data and resolve the time request
+        let update = app.update(Event::GetNational, &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // resolve the intensity request with a server error
+        let update = app.update(update.events[0].clone(), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let response = HttpResponse::status(503)
+            .body("Service Unavailable")
+            .build();
+        let update = app.resolve(request, response).unwrap();
+
+        // check the status is reported, the model is updated and we render
+        let actual = &update.events;
+        let expected = &vec![Event::SetNational(0, Err(Error::Status(503)))];
+        assert_eq!(actual, expected);
+
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(model.national.error, Some(Error::Status(503)));
+        assert_eq!(
+            app.view(&model).national_error,
+            Some("Server responded with status 503".to_string())
+        );
+        assert_eq!(app.view(&model).local_error, None);
+
+        // the mix arriving doesn't clear the error, or bring the data up to date
+        let mix: NationalMixResponse =
+            serde_json::from_str(include_str!("./fixtures/national_mix.json")).unwrap();
+        app.update(Event::SetNationalMix(0, Ok(mix.clone())), &mut model);
+        assert_eq!(model.national.error, Some(Error::Status(503)));
+        assert_eq!(model.national.last_updated, DateTime::<Utc>::default());
+
+        // but once both parts succeed, the error is cleared
+        model.national.start(Operation::NationalIntensity);
+        model.national.start(Operation::NationalMix);
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+        assert_eq!(model.national.error, Some(Error::Status(503)));
+        app.update(Event::SetNationalMix(0, Ok(mix)), &mut model);
+        assert_eq!(app.view(&model).national_error, None);
+        assert_eq!(model.national.last_updated, model.time);
+    }
+
+    #[test]
+    fn notify_when_forecast_drops_below_threshold() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        model.time = DateTime::parse_from_rfc3339("2023-07-04T23:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        app.update(
+            Event::AddThreshold(Threshold {
+                name: "Low carbon".to_string(),
+                scope: Mode::National,
+                condition: Condition::Below(140),
+                warning_minutes: 15,
+            }),
+            &mut model,
+        );
+
+        // check we schedule a notification for the period that's below it
+        let update = app.update(Event::SetNational(0, Ok(national.clone())), &mut model);
+        let requests: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_notify)
+            .map(|request| request.operation)
+            .collect();
+        assert_eq!(
+            requests,
+            vec![NotificationRequest::Schedule(Notification {
+                id: "Low carbon@2023-07-05T00:00:00+00:00".to_string(),
+                title: "Low carbon".to_string(),
+                body: "Carbon intensity is forecast to be below 140 gCO2/kWh from 01:00 BST"
+                    .to_string(),
+                at: "2023-07-04T23:45:00+00:00".to_string(),
+            })]
+        );
+
+        // but not again when the forecast is refreshed
+        let update = app.update(Event::SetNational(0, Ok(national)), &mut model);
+        assert!(update
+            .into_effects()
+            .find_map(Effect::into_notify)
+            .is_none());
+
+        // and cancel it if the threshold is removed
+        let update = app.update(Event::RemoveThreshold("Low carbon".to_string()), &mut model);
+        let request = update.into_effects().find_map(Effect::into_notify).unwrap();
+        assert_eq!(
+            request.operation,
+            NotificationRequest::Cancel("Low carbon@2023-07-05T00:00:00+00:00".to_string())
+        );
+        assert!(model.notified.is_empty());
+    }
+
+    #[test]
+    fn reject_threshold_with_invalid_warning() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let threshold = |warning_minutes| Threshold {
+            name: "Low carbon".to_string(),
+            scope: Mode::National,
+            condition: Condition::Below(140),
+            warning_minutes,
+        };
+
+        for warning_minutes in [-15, i64::MAX] {
+            let update = app.update(Event::AddThreshold(threshold(warning_minutes)), &mut model);
+            assert_effect!(update, Effect::Render(_));
+            assert!(model.thresholds.is_empty());
+            assert_eq!(
+                app.view(&model).threshold_error,
+                Some(format!(
+                    "Can't warn {warning_minutes} minutes before (0 to 1440)"
+                ))
+            );
+        }
+
+        app.update(Event::AddThreshold(threshold(15)), &mut model);
+        assert_eq!(model.thresholds.len(), 1);
+        assert_eq!(app.view(&model).threshold_error, None);
+    }
+
+    #[test]
+    fn cancel_notification_when_forecast_no_longer_meets_threshold() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        model.time = DateTime::parse_from_rfc3339("2023-07-04T23:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        app.update(
+            Event::AddThreshold(Threshold {
+                name: "Low carbon".to_string(),
+                scope: Mode::National,
+                condition: Condition::Below(140),
+                warning_minutes: 15,
+            }),
+            &mut model,
+        );
+        app.update(Event::SetNational(0, Ok(national.clone())), &mut model);
+        assert_eq!(model.notified.len(), 1);
+
+        // the forecast is revised up, so the period no longer qualifies
+        let mut revised = national;
+        for period in &mut revised.data {
+            if let Some(intensity) = &mut period.intensity {
+                intensity.forecast = intensity.forecast.max(200);
+            }
+        }
+        let update = app.update(Event::SetNational(0, Ok(revised)), &mut model);
+        let requests: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_notify)
+            .map(|request| request.operation)
+            .collect();
+        assert_eq!(
+            requests,
+            vec![NotificationRequest::Cancel(
+                "Low carbon@2023-07-05T00:00:00+00:00".to_string()
+            )]
+        );
+        assert!(model.notified.is_empty());
+    }
+
+    #[test]
+    fn background_refresh_without_rendering() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::BackgroundRefresh, &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // only the national data is fetched (and the emission factors), as we
+        // don't know where the user is
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert!(update
+            .effects
+            .iter()
+            .all(|effect| matches!(effect, Effect::Http(_))));
+        let mut requests: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_http)
+            .collect();
+        assert_eq!(requests.len(), 4);
+
+        // the past 24 hours are only for comparison, so failing to fetch
+        // them doesn't hold anything up
+        let response = HttpResponse::status(503).build();
+        let update = app.resolve(&mut requests[2], response).unwrap();
+        app.update(update.events[0].clone(), &mut model);
+
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let response = HttpResponse::ok().json(&national).build();
+        let update = app.resolve(&mut requests[0], response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert!(update
+            .effects
+            .iter()
+            .all(|effect| !matches!(effect, Effect::Render(_) | Effect::Background(_))));
+
+        let mix: NationalMixResponse =
+            serde_json::from_str(include_str!("./fixtures/national_mix.json")).unwrap();
+        let response = HttpResponse::ok().json(&mix).build();
+        let update = app.resolve(&mut requests[1], response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+
+        // once both have arrived, we tell the shell we're done
+        assert!(update
+            .effects
+            .iter()
+            .all(|effect| !matches!(effect, Effect::Render(_))));
+        let request = update
+            .into_effects()
+            .find_map(Effect::into_background)
+            .unwrap();
+        assert_eq!(
+            request.operation,
+            RefreshComplete {
+                changed: true,
+                next_refresh: "2023-07-05T00:15:00+00:00".to_string(),
+            }
+        );
+        assert_eq!(model.background, None);
+    }
+
+    #[test]
+    fn render_once_the_shell_sends_another_event() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+
+        app.update(Event::BackgroundRefresh, &mut model);
+        app.update(Event::SetTimeBackground(time), &mut model);
+
+        // the background refresh's own responses don't render
+        let update = app.update(Event::SetNational(0, Err(Error::Status(503))), &mut model);
+        assert!(update
+            .effects
+            .iter()
+            .all(|effect| !matches!(effect, Effect::Render(_))));
+
+        // but once the app is in use again, everything does
+        let update = app.update(Event::SetZone(Zone::Utc), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        app.update(
+            Event::SetNationalMix(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        let update = app.update(
+            Event::SetNationalPast(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        assert_effect!(update, Effect::Background(_));
+    }
+
+    #[test]
+    fn background_refresh_when_nothing_is_due() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        set_time(time.clone(), &mut model);
+        model.national.last_updated = model.time - chrono::Duration::minutes(10);
+
+        app.update(Event::BackgroundRefresh, &mut model);
+        let update = app.update(Event::SetTimeBackground(time), &mut model);
+
+        let effects: Vec<_> = update.into_effects().collect();
+        assert_eq!(effects.len(), 1);
+        let request = effects
+            .into_iter()
+            .find_map(Effect::into_background)
+            .unwrap();
+        assert_eq!(
+            request.operation,
+            RefreshComplete {
+                changed: false,
+                next_refresh: "2023-07-05T00:05:00+00:00".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn background_refresh_with_no_postcode_nearby() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        set_time(time.clone(), &mut model);
+        model.national.last_updated = model.time;
+        model.local.scope.location = Some(Location::default());
+
+        app.update(Event::BackgroundRefresh, &mut model);
+        let update = app.update(Event::SetTimeBackground(time), &mut model);
+        assert_effect!(update, Effect::GetLocation(_));
+
+        let generation = model.local.generation;
+        let location = capabilities::location::Coordinate {
+            latitude: 51.403366,
+            longitude: -0.298302,
+        };
+        app.update(
+            Event::SetLocation(
+                generation,
+                LocationResponse {
+                    location: Some(location),
+                },
+            ),
+            &mut model,
+        );
+        let response = PostcodeResponse {
+            status: 200,
+            result: None,
+        };
+        let update = app.update(Event::SetPostcode(generation, Ok(response)), &mut model);
+
+        // the shell is still told we're done
+        assert_effect!(update, Effect::Background(_));
+        assert_eq!(model.local.error, Some(Error::NoPostcode));
+        assert_eq!(model.background, None);
+    }
+
+    #[test]
+    fn compare_local_and_national() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // check we ask for the time for both
+        let update = app.update(Event::GetComparison, &mut model);
+        assert_eq!(model.mode, Mode::Compare);
+        assert_eq!(
+            update.into_effects().filter_map(Effect::into_time).count(),
+            2
+        );
+
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.update(
+            Event::SetTimeRefresh(Mode::Compare, false, time),
+            &mut model,
+        );
+        assert_effect!(update, Effect::GetLocation(_));
+        assert_effect!(update, Effect::Http(_));
+
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let regional: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+        app.update(
+            Event::SetRegional(Place::Current, 0, Ok(regional)),
+            &mut model,
+        );
+
+        let view = app.view(&model);
+        assert_eq!(view.comparison.len(), 2);
+        let current = view.comparison_current.unwrap();
+        assert_eq!(current.date, "2023-07-04T23:30:00+00:00");
+        assert_eq!(current.difference, 121 - 142);
+        assert_eq!(
+            view.comparison_headline,
+            Some("Your region is 15% cleaner than the UK average right now".to_string())
+        );
+    }
+
+    #[test]
+    fn compare_two_sites() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // an invalid site is reported without fetching anything
+        let update = app.update(
+            Event::CompareSites {
+                first: "KT1 2UD".to_string(),
+                second: "15".to_string(),
+                load_kw: 2.0,
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(
+            app.view(&model).sites_error,
+            Some("\"15\" is not a region ID (1 to 14)".to_string())
+        );
+
+        // as is a load that would make the totals meaningless
+        let update = app.update(
+            Event::CompareSites {
+                first: "KT1 2UD".to_string(),
+                second: "13".to_string(),
+                load_kw: f64::NAN,
+            },
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(
+            app.view(&model).sites_error,
+            Some("\"NaN\" is not a valid amount of energy".to_string())
+        );
+
+        let update = app.update(
+            Event::CompareSites {
+                first: "KT1 2UD".to_string(),
+                second: "13".to_string(),
+                load_kw: 2.0,
+            },
+            &mut model,
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // check we request both regional forecasts
+        let update = app.update(update.events[0].clone(), &mut model);
+        let urls: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_http)
+            .map(|request| request.operation.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/fw24h/postcode/KT1",
+                "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/fw24h/regionid/13",
+            ]
+        );
+        assert!(app.view(&model).sites_loading);
+
+        let first: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let mut second = first.clone();
+        second.data.region_id = 13;
+        second.data.shortname = "London".to_string();
+        for period in &mut second.data.data {
+            period.intensity.as_mut().unwrap().forecast += 20;
+        }
+        let generation = model.sites.generation;
+        app.update(Event::SetSite(1, generation, Ok(second)), &mut model);
+        app.update(Event::SetSite(0, generation, Ok(first)), &mut model);
+
+        let view = app.view(&model);
+        assert!(!view.sites_loading);
+        let comparison = view.site_comparison.unwrap();
+        assert_eq!(comparison.first_name, "KT1 2UD (South East England)");
+        assert_eq!(comparison.second_name, "London");
+        let cumulative: Vec<_> = comparison
+            .periods
+            .iter()
+            .map(|point| point.cumulative_grams)
+            .collect();
+        // 2 kW for half an hour is 1 kWh, so 20 g less in each period
+        assert_eq!(cumulative, vec![-20.0, -40.0]);
+        assert_eq!(comparison.total_grams, -40.0);
+
+        app.update(Event::ClearSites, &mut model);
+        assert!(app.view(&model).site_comparison.is_none());
+    }
+
+    #[test]
+    fn compare_sites_when_one_fails() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        app.update(
+            Event::CompareSites {
+                first: "KT1 2UD".to_string(),
+                second: "13".to_string(),
+                load_kw: 2.0,
+            },
+            &mut model,
+        );
+        let update = app.update(
+            Event::SetTimeSites(TimeResponse("2023-07-04T23:45:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+
+        let forecast: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let generation = model.sites.generation;
+        app.update(
+            Event::SetSite(0, generation, Err(Error::Status(500))),
+            &mut model,
+        );
+        app.update(Event::SetSite(1, generation, Ok(forecast)), &mut model);
+
+        // the other site arriving doesn't hide the failure
+        assert_eq!(
+            app.view(&model).sites_error,
+            Some("Server responded with status 500".to_string())
+        );
+        assert_eq!(model.sites.failures.count, 1);
+        assert_eq!(model.sites.last_updated, DateTime::<Utc>::default());
+    }
+
+    #[test]
+    fn compare_the_day_with_yesterday() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let update = app.update(
+            Event::SetTimeNational(TimeResponse("2023-07-04T23:45:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+
+        let periods = |start: DateTime<Utc>, forecast: i32| -> Vec<Period> {
+            (0..48)
+                .map(|i| Period {
+                    from: start + Duration::minutes(30 * i),
+                    to: start + Duration::minutes(30 * (i + 1)),
+                    intensity: Some(Intensity {
+                        forecast,
+                        actual: None,
+                        index: IntensityIndex::Moderate,
+                    }),
+                    generation_mix: None,
+                })
+                .collect()
+        };
+        let start = model.time - Duration::minutes(15);
+        let national = NationalResponse {
+            data: periods(start, 120),
+        };
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+        let past = NationalResponse {
+            data: periods(start - Duration::days(1), 100),
+        };
+        let update = app.update(Event::SetNationalPast(0, Ok(past)), &mut model);
+        assert_effect!(update, Effect::Render(_));
+
+        let summary = app.view(&model).daily_summary.unwrap();
+        assert_eq!(summary.average, 120.0);
+        assert_eq!(summary.yesterday_average, Some(100.0));
+        assert_eq!(summary.change_from_yesterday_percent, Some(20.0));
+    }
+
+    #[test]
+    fn label_periods_in_local_time() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        set_time(TimeResponse("2023-10-28T23:10:00Z".to_string()), &mut model);
+
+        // from midnight BST on the day the clocks go back
+        let start = model.time - Duration::minutes(10);
+        let national = NationalResponse {
+            data: (0..52)
+                .map(|i| Period {
+                    from: start + Duration::minutes(30 * i),
+                    to: start + Duration::minutes(30 * (i + 1)),
+                    intensity: Some(Intensity {
+                        forecast: 100,
+                        actual: None,
+                        index: IntensityIndex::Low,
+                    }),
+                    generation_mix: None,
+                })
+                .collect(),
+        };
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+
+        let view = app.view(&model);
+        assert_eq!(view.now_label, "00:10 BST");
+        let days: Vec<_> = view
+            .days
+            .iter()
+            .map(|day| (day.label.as_str(), day.half_hours))
+            .collect();
+        assert_eq!(days, vec![("Sun 29 Oct", 50), ("Mon 30 Oct", 48)]);
+
+        let points = &view.national_intensity;
+        assert!(points[0].now && points[0].starts_day);
+        let labels: Vec<_> = points[..6].iter().map(|p| p.label.as_str()).collect();
+        // the hour from 01:00 happens twice
+        assert_eq!(
+            labels,
+            vec!["00:00", "00:30", "01:00", "01:30", "01:00", "01:30"]
+        );
+        assert!(points[50].starts_day);
+        assert_eq!(points.iter().filter(|p| p.starts_day).count(), 2);
+
+        app.update(Event::SetZone(Zone::Utc), &mut model);
+
+        let view = app.view(&model);
+        assert_eq!(view.now_label, "23:10 UTC");
+        assert_eq!(view.national_intensity[0].label, "23:00");
+        assert!(view.national_intensity[2].starts_day);
+        assert_eq!(view.days[0].date, "2023-10-28");
+    }
+
+    #[test]
+    fn serve_stale_data_when_offline() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        model.time = DateTime::parse_from_rfc3339("2023-07-04T23:45:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+
+        // an hour later, the refresh fails
+        let update = app.update(
+            Event::SetTimeNational(TimeResponse("2023-07-05T00:45:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+        app.update(
+            Event::SetNational(0, Err(Error::Network("offline".to_string()))),
+            &mut model,
+        );
+
+        // check we still show the data, but say how old it is
+        let view = app.view(&model);
+        assert_eq!(view.national_freshness, Freshness::Stale);
+        assert_eq!(
+            view.national_as_of,
+            Some("2023-07-04T23:45:00+00:00".to_string())
+        );
+        assert!(view.national_current.is_none());
+        assert!(view.national_intensity.is_empty());
+    }
+
+    #[test]
+    fn no_duplicate_requests_while_loading() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // asking twice only asks for the time once
+        let update = app.update(Event::GetNational, &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let update = app.update(Event::GetNational, &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+        assert!(app.view(&model).national_loading);
+
+        // and a late time response doesn't fetch the data twice
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.resolve(request, response.clone()).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_eq!(
+            update.into_effects().filter_map(Effect::into_http).count(),
+            4
+        );
+        let update = app.update(Event::SetTimeNational(response), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+        assert_eq!(
+            model.national.pending,
+            vec![
+                Operation::NationalIntensity,
+                Operation::NationalMix,
+                Operation::Past
+            ]
+        );
+
+        // the flag is cleared once all the requests have finished
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+        assert!(app.view(&model).national_loading);
+        app.update(
+            Event::SetNationalMix(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        assert!(app.view(&model).national_loading);
+        app.update(
+            Event::SetNationalPast(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        assert!(!app.view(&model).national_loading);
+    }
+
+    #[test]
+    fn request_factors_once() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let factors_requests = |update: crux_core::testing::Update<Effect, Event>| {
+            update
+                .into_effects()
+                .filter_map(Effect::into_http)
+                .filter(|request| request.operation.url.ends_with("/intensity/factors"))
+                .count()
+        };
+
+        let update = app.update(Event::SetTimeNational(time.clone()), &mut model);
+        assert_eq!(factors_requests(update), 1);
+
+        // fetching the local data while the factors are on their way doesn't
+        // ask for them again
+        let update = app.update(Event::SetTimeLocal(time.clone()), &mut model);
+        assert_eq!(factors_requests(update), 0);
+
+        // but if the request fails, the next refresh tries again
+        app.update(Event::SetFactors(Err(Error::Status(503))), &mut model);
+        app.update(Event::SetNational(0, Err(Error::Status(503))), &mut model);
+        app.update(
+            Event::SetNationalMix(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        app.update(
+            Event::SetNationalPast(0, Err(Error::Status(503))),
+            &mut model,
+        );
+        let update = app.update(
+            Event::SetTimeRefresh(Mode::National, true, time),
+            &mut model,
+        );
+        assert_eq!(factors_requests(update), 1);
+    }
+
+    #[test]
+    fn force_refresh() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        set_time(time.clone(), &mut model);
+        model.national.last_updated = model.time;
+
+        // the data is fresh, so a normal refresh does nothing
+        let update = app.update(
+            Event::Refresh {
+                scope: Mode::National,
+                force: false,
+            },
+            &mut model,
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let update = app.resolve(request, time.clone()).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+
+        // but a forced one fetches it again
+        let update = app.update(
+            Event::SetTimeRefresh(Mode::National, true, time.clone()),
+            &mut model,
+        );
+        assert_eq!(
+            update.into_effects().filter_map(Effect::into_http).count(),
+            4
+        );
+        assert!(model.national.is_loading());
+
+        // and isn't repeated while the requests are in flight
+        let update = app.update(
+            Event::SetTimeRefresh(Mode::National, true, time.clone()),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let mix: NationalMixResponse =
+            serde_json::from_str(include_str!("./fixtures/national_mix.json")).unwrap();
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+        app.update(Event::SetNationalMix(0, Ok(mix)), &mut model);
+        app.update(
+            Event::SetNationalPast(0, Ok(Default::default())),
+            &mut model,
+        );
+        assert!(!model.national.is_loading());
+    }
+
+    #[test]
+    fn fetch_48_hour_forecast() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        app.update(Event::SetNational(0, Ok(national)), &mut model);
+
+        // the 24-hour forecast is discarded and fetched again
+        let update = app.update(Event::SetHorizon(Horizon::Hours48), &mut model);
+        assert!(model.national.periods.is_empty());
+        assert_eq!(app.view(&model).horizon, Horizon::Hours48);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        let update = app.update(update.events[0].clone(), &mut model);
+        let urls: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_http)
+            .map(|request| request.operation.url)
+            .collect();
+        assert_eq!(
+            urls[..2],
+            [
+                "https://api.carbonintensity.org.uk/intensity/2023-07-04T23:45Z/fw48h",
+                "https://api.carbonintensity.org.uk/generation/2023-07-04T23:45Z/2023-07-06T23:45Z",
+            ]
+        );
+
+        // setting the same horizon again doesn't fetch anything
+        let update = app.update(Event::SetHorizon(Horizon::Hours48), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+    }
+
+    #[test]
+    fn change_horizon_while_comparing_sites() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mix: NationalMixResponse =
+            serde_json::from_str(include_str!("./fixtures/national_mix.json")).unwrap();
+        app.update(Event::SetNationalMix(0, Ok(mix)), &mut model);
+        app.update(
+            Event::CompareSites {
+                first: "KT1 2UD".to_string(),
+                second: "13".to_string(),
+                load_kw: 2.0,
+            },
+            &mut model,
+        );
+        let forecast: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let generation = model.sites.generation;
+        app.update(
+            Event::SetSite(0, generation, Ok(forecast.clone())),
+            &mut model,
+        );
+        app.update(Event::SetSite(1, generation, Ok(forecast)), &mut model);
+
+        // the mix and both sites' forecasts are discarded, and the sites are
+        // fetched again
+        app.update(Event::SetHorizon(Horizon::Hours48), &mut model);
+        assert!(model.national.scope.generation_mix.is_empty());
+        assert!(model
+            .sites
+            .scope
+            .forecasts
+            .iter()
+            .all(|forecast| forecast.periods.is_empty()));
+        assert!(model.sites.is_pending(Operation::Time));
+
+        let update = app.update(
+            Event::SetTimeSites(TimeResponse("2023-07-04T23:45:00Z".to_string())),
+            &mut model,
+        );
+        let urls: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_http)
+            .map(|request| request.operation.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/fw48h/postcode/KT1",
+                "https://api.carbonintensity.org.uk/regional/intensity/2023-07-04T23:45Z/fw48h/regionid/13",
+            ]
+        );
+    }
+
+    #[test]
+    fn force_refresh_local_finds_location_again() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let time = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        set_time(time.clone(), &mut model);
+        model.local.last_updated = model.time;
+
+        let update = app.update(Event::SetTimeRefresh(Mode::Local, true, time), &mut model);
+
+        assert_effect!(update, Effect::GetLocation(_));
+        assert!(model.local.is_loading());
+
+        // the chain of requests ends if we can't get the location
+        app.update(
+            Event::SetLocation(0, LocationResponse { location: None }),
+            &mut model,
+        );
+        assert!(!model.local.is_loading());
+    }
+
+    #[test]
+    fn ignore_late_postcode_after_entering_postcode() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // start finding the device's postcode
+        let update = app.update(
+            Event::SetTimeLocal(TimeResponse("2023-07-06T20:30:00Z".to_string())),
+            &mut model,
+        );
+        let request = &mut update
+            .into_effects()
+            .find_map(Effect::into_location)
+            .unwrap();
+        let response = LocationResponse {
+            location: Some(capabilities::location::Coordinate {
+                latitude: 51.403366,
+                longitude: -0.298302,
+            }),
+        };
+        let update = app.resolve(request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        let postcode_request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+
+        // the user enters a postcode before the device's postcode arrives
+        let update = app.update(Event::EnterPostcode("G1".to_string()), &mut model);
+        let time_request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+
+        // the late response doesn't change anything or start another request
+        let postcode: PostcodeResponse =
+            serde_json::from_str(include_str!("./fixtures/postcode.json")).unwrap();
+        let response = HttpResponse::ok().json(&postcode).build();
+        let update = app.resolve(postcode_request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert!(update.effects.is_empty());
+        assert_eq!(model.local.scope.location, None);
+        assert!(!model.local.is_fetching());
+
+        // and the entered postcode is still looked up
+        let response = TimeResponse("2023-07-06T20:31:00Z".to_string());
+        let update = app.resolve(time_request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let actual = &request.operation;
+        let expected = &HttpRequest::get("https://api.postcodes.io/outcodes/G1").build();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn ignore_late_regional_after_using_device_location() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // look up an outcode, which gets us as far as the regional request
+        let update = app.update(Event::EnterPostcode("G1".to_string()), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-04T23:45:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let response = HttpResponse::ok()
+            .body(include_str!("./fixtures/outcode.json"))
+            .build();
+        let update = app.resolve(request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        let regional_request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+
+        // switch back to the device's location while it's in flight
+        app.update(Event::UseDeviceLocation, &mut model);
+
+        // so the forecast for the outcode is ignored when it arrives
+        let regional: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let response = HttpResponse::ok().json(&regional).build();
+        let update = app.resolve(regional_request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert!(update.effects.is_empty());
+        assert!(model.local.periods.is_empty());
+        assert_eq!(model.local.last_updated, DateTime::<Utc>::default());
+        assert!(!model.local.is_fetching());
+    }
+
+    #[test]
+    fn ignore_late_history_for_previous_range() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // ask for one day, then another before the first arrives
+        let generation = model.history.generation;
+        app.update(
+            Event::GetHistoryForDate("2023-07-04".to_string()),
+            &mut model,
+        );
+        app.update(
+            Event::GetHistoryForDate("2023-07-03".to_string()),
+            &mut model,
+        );
+
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let range = HistoryRange::Date(NaiveDate::from_ymd_opt(2023, 7, 4).unwrap());
+        let update = app.update(
+            Event::SetHistory(range, generation, Ok(national)),
+            &mut model,
+        );
+
+        assert!(update.effects.is_empty());
+        assert!(model.history.periods.is_empty());
+    }
+
+    #[test]
+    fn refresh_policy_out_of_range() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        set_time(TimeResponse("2023-07-04T23:45:00Z".to_string()), &mut model);
+
+        app.update(
+            Event::SetRefreshPolicy(RefreshPolicy {
+                min_age_minutes: i64::MAX,
+                backoff_minutes: -1,
+                ..Default::default()
+            }),
+            &mut model,
+        );
+
+        assert_eq!(model.refresh.min_age_minutes, refresh::MAX_MINUTES);
+        assert_eq!(model.refresh.backoff_minutes, 0);
+        assert_eq!(app.view(&model).national_freshness, Freshness::NeverLoaded);
+    }
+
+    #[test]
+    fn back_off_after_failed_refresh() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        app.update(
+            Event::SetRefreshPolicy(RefreshPolicy {
+                backoff_minutes: 5,
+                ..Default::default()
+            }),
+            &mut model,
+        );
+
+        // the first attempt fails, even though the mix arrives
+        let update = app.update(
+            Event::SetTimeNational(TimeResponse("2023-07-04T23:45:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+        app.update(Event::SetNational(0, Err(Error::Status(503))), &mut model);
+        let mix: NationalMixResponse =
+            serde_json::from_str(include_str!("./fixtures/national_mix.json")).unwrap();
+        app.update(Event::SetNationalMix(0, Ok(mix)), &mut model);
+        app.update(
+            Event::SetNationalPast(0, Ok(Default::default())),
+            &mut model,
+        );
+        assert_eq!(model.national.failures.count, 1);
+
+        // so we don't try again straight away
+        let update = app.update(
+            Event::SetTimeNational(TimeResponse("2023-07-04T23:49:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+
+        // but do once the backoff has passed
+        let update = app.update(
+            Event::SetTimeNational(TimeResponse("2023-07-04T23:50:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+    }
+
+    #[test]
+    fn local_location_denied() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::SetLocation(0, LocationResponse { location: None }),
+            &mut model,
+        );
+
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(model.local.error, Some(Error::LocationDenied));
+        assert_eq!(
+            app.view(&model).local_error,
+            Some("Location access was denied".to_string())
+        );
+    }
+
+    #[test]
+    fn local_no_postcode_for_location() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // request a postcode for a location in the sea
+        let update = app.update(
+            Event::SetLocation(
+                0,
+                LocationResponse {
+                    location: Some(capabilities::location::Coordinate {
+                        latitude: 50.0,
+                        longitude: -5.0,
+                    }),
+                },
+            ),
+            &mut model,
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+
+        // postcodes.io responds with a null result
+        let response = HttpResponse::ok()
+            .body(r#"{ "status": 200, "result": null }"#)
+            .build();
+        let update = app.resolve(request, response).unwrap();
+
+        // check we don't make a regional request, and report the error
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert!(update.into_effects().find_map(Effect::into_http).is_none());
+        assert_eq!(model.local.error, Some(Error::NoPostcode));
+    }
+
+    #[test]
+    fn local_malformed_regional_response() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // resolve the postcode so that we get a regional request
+        let postcode: PostcodeResponse =
+            serde_json::from_str(include_str!("./fixtures/postcode.json")).unwrap();
+        let update = app.update(Event::SetPostcode(0, Ok(postcode)), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+
+        // resolve the regional request with something unexpected
+        let response = HttpResponse::ok().body(r#"{ "data": [] }"#).build();
+        let update = app.resolve(request, response).unwrap();
+
+        assert_matches!(
+            update.events[0].clone(),
+            Event::SetRegional(Place::Current, _, Err(Error::Decode(_)))
+        );
+        app.update(update.events[0].clone(), &mut model);
+        assert_matches!(model.local.error, Some(Error::Decode(_)));
+        assert!(model.local.periods.is_empty());
+    }
+
+    #[test]
+    fn local_for_entered_outcode() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // enter an outcode and check we get a time request
+        let update = app.update(Event::EnterPostcode(" g1 ".to_string()), &mut model);
+        assert_eq!(model.mode, Mode::Local);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // check that we look up the outcode instead of asking for the device location
+        // (the other request is for the emission factors)
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_eq!(update.effects.len(), 2);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let actual = &request.operation;
+        let expected = &HttpRequest::get("https://api.postcodes.io/outcodes/G1").build();
+        assert_eq!(actual, expected);
+
+        // resolve a simulated outcode response
+        let response = HttpResponse::ok()
+            .body(include_str!("./fixtures/outcode.json"))
+            .build();
+        let update = app.resolve(request, response).unwrap();
+
+        // check that the location is set and we get a regional request
+        let update = app.update(update.events[0].clone(), &mut model);
+        assert_eq!(
+            model.local.scope.location.clone().unwrap(),
+            Location {
+                coordinate: Coordinate {
+                    latitude: 55.8595521728395,
+                    longitude: -4.24914380839161,
+                },
+                outcode: "G1".to_string(),
+                admin_district: "Glasgow City".to_string(),
+            }
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let actual = &request.operation;
+        let expected = &HttpRequest::get(
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/fw24h/postcode/G1",
+        )
+        .build();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn enter_invalid_postcode() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::EnterPostcode("not a postcode".to_string()),
+            &mut model,
+        );
+
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+        assert_eq!(
+            app.view(&model).local_error,
+            Some("\"not a postcode\" is not a valid postcode".to_string())
+        );
+    }
+
+    #[test]
+    fn enter_unknown_postcode() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        app.update(Event::EnterPostcode("ZZ9 9ZZ".to_string()), &mut model);
+        let update = app.update(
+            Event::SetTimeLocal(TimeResponse("2023-07-06T20:30:00Z".to_string())),
+            &mut model,
+        );
+
+        // postcodes.io responds with a 404 for postcodes that don't exist
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let response = HttpResponse::status(404)
+            .body(r#"{ "status": 404, "error": "Invalid postcode" }"#)
+            .build();
+        let update = app.resolve(request, response).unwrap();
+        app.update(update.events[0].clone(), &mut model);
+
+        assert_eq!(
+            model.local.error,
+            Some(Error::InvalidPostcode("ZZ9 9ZZ".to_string()))
+        );
+        assert!(model.local.scope.location.is_none());
+    }
+
+    #[test]
+    fn saved_locations() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // add a couple of locations, each of which is selected as it's added
+        for (name, postcode) in [("Home", "KT1 2UD"), ("Office", "g1")] {
+            let update = app.update(
+                Event::AddLocation {
+                    name: name.to_string(),
+                    postcode: postcode.to_string(),
+                },
+                &mut model,
+            );
+            assert!(update.into_effects().find_map(Effect::into_time).is_some());
+            assert_eq!(model.place, Place::Saved(name.to_string()));
+        }
+        assert_eq!(model.mode, Mode::Local);
+
+        // fetch the office's forecast, which looks up its outcode
+        let update = app.update(
+            Event::SetTimeLocal(TimeResponse("2023-07-05T00:10:00Z".to_string())),
+            &mut model,
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let actual = &request.operation;
+        let expected = &HttpRequest::get("https://api.postcodes.io/outcodes/G1").build();
+        assert_eq!(actual, expected);
+        let response = HttpResponse::ok()
+            .body(include_str!("./fixtures/outcode.json"))
+            .build();
+        let update = app.resolve(request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+
+        // switch to home before the office's regional response arrives
+        app.update(Event::SelectLocation(Some("Home".to_string())), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_http).unwrap();
+        let simulated_response: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let response = HttpResponse::ok().json(&simulated_response).build();
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        // the response is stored against the office, not the selected location
+        assert_eq!(model.saved[1].data.periods.len(), 2);
+        assert!(model.saved[0].data.periods.is_empty());
+        assert!(model.local.periods.is_empty());
+
+        // move the office to the top of the list
+        app.update(
+            Event::MoveLocation {
+                name: "Office".to_string(),
+                index: 0,
+            },
+            &mut model,
+        );
+        insta::assert_yaml_snapshot!(app.view(&model).saved_locations, @r###"
+        ---
+        - name: Office
+          area: "Glasgow City, G1"
+          forecast: 116
+          index: Low
+          selected: false
+        - name: Home
+          area: ~
+          forecast: ~
+          index: ~
+          selected: true
+        "###);
+
+        // removing the selected location selects the current location
+        app.update(Event::RemoveLocation("Home".to_string()), &mut model);
+        assert_eq!(model.place, Place::Current);
+        assert_eq!(model.saved.len(), 1);
+        assert_eq!(app.view(&model).local_name, "Local");
+    }
+
+    #[test]
+    fn ignore_late_regional_for_a_removed_location() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let add = |postcode: &str| Event::AddLocation {
+            name: "Office".to_string(),
+            postcode: postcode.to_string(),
+        };
+        let office = Place::Saved("Office".to_string());
+
+        app.update(add("G1 1AA"), &mut model);
+        let generation = model.local(&office).unwrap().generation;
+
+        // remove the office and add it again somewhere else before the old
+        // postcode's forecast arrives
+        app.update(Event::RemoveLocation("Office".to_string()), &mut model);
+        app.update(add("KT1 2UD"), &mut model);
+        assert_ne!(model.local(&office).unwrap().generation, generation);
+
+        let response: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        app.update(
+            Event::SetRegional(office.clone(), generation, Ok(response)),
+            &mut model,
+        );
+        assert!(model.local(&office).unwrap().periods.is_empty());
+    }
+
+    #[test]
+    fn browse_regions() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // request all regions and resolve the time request
+        let update = app.update(Event::GetRegions, &mut model);
+        assert_eq!(model.mode, Mode::Regions);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-05T00:10:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // check we only request the current intensity of every region
+        let update = app.update(update.events[0].clone(), &mut model);
+        let requests = &mut update.into_effects().filter_map(Effect::into_http);
+        let request = &mut requests.next().unwrap();
+        assert!(requests.next().is_none());
+        let actual = &request.operation;
+        let expected = &HttpRequest::get("https://api.carbonintensity.org.uk/regional").build();
+        assert_eq!(actual, expected);
+
+        // resolve a simulated response and check only the DNO regions are shown
+        let simulated_response: RegionsResponse =
+            serde_json::from_str(include_str!("./fixtures/regions.json")).unwrap();
+        let response = HttpResponse::ok().json(&simulated_response).build();
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        insta::assert_yaml_snapshot!(app.view(&model).regions, @r###"
+        ---
+        - id: 1
+          name: North Scotland
+          dno_region: Scottish Hydro Electric Power Distribution
+          forecast: 0
+          index: VeryLow
+          selected: false
+        - id: 13
+          name: London
+          dno_region: UKPN London
+          forecast: 169
+          index: Moderate
+          selected: false
+        - id: 14
+          name: South East England
+          dno_region: UKPN South East
+          forecast: 116
+          index: Low
+          selected: false
+        "###);
+
+        // drill into a region and check we only request its forecast and the
+        // past 24 hours to compare it with
+        let update = app.update(Event::SelectRegion(Some(14)), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-05T00:15:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+        let update = app.update(update.events[0].clone(), &mut model);
+        let requests = &mut update.into_effects().filter_map(Effect::into_http);
+        let request = &mut requests.next().unwrap();
+        let past = requests.next().unwrap();
+        assert!(requests.next().is_none());
+        let actual = &request.operation;
+        let expected = &HttpRequest::get(
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-05T00:15Z/fw24h/regionid/14",
+        )
+        .build();
+        assert_eq!(actual, expected);
+        let actual = &past.operation;
+        let expected = &HttpRequest::get(
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-05T00:15Z/pt24h/regionid/14",
+        )
+        .build();
+        assert_eq!(actual, expected);
+
+        // resolve a simulated forecast
+        let simulated_response: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+        let response = HttpResponse::ok().json(&simulated_response).build();
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        let view = app.view(&model);
+        assert_eq!(view.region_name, "South East England");
+        // the period that has elapsed is hidden
+        assert_eq!(view.region_intensity.len(), 1);
+        assert_eq!(
+            view.region_current.unwrap().date,
+            "2023-07-05T00:00:00+00:00"
+        );
+        assert_eq!(view.region_freshness, Freshness::Fresh);
+        assert!(view.regions.iter().any(|region| region.selected));
+
+        // a forecast for a region that's no longer selected is ignored
+        let generation = model.regions.generation;
+        app.update(Event::SelectRegion(Some(13)), &mut model);
+        app.update(
+            Event::SetRegion(generation, Ok(simulated_response.clone())),
+            &mut model,
+        );
+        assert!(model.regions.periods.is_empty());
+    }
+
+    #[test]
+    fn regions_list_and_forecast_fail_separately() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        app.update(Event::SelectRegion(Some(13)), &mut model);
+        let update = app.update(
+            Event::SetTimeRegions(TimeResponse("2023-07-05T00:15:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+        let generation = model.regions.generation;
+        let forecast: RegionalResponse =
+            serde_json::from_str(include_str!("./fixtures/regional.json")).unwrap();
+
+        // the list failing isn't hidden by the forecast arriving
+        app.update(Event::SetRegions(Err(Error::Status(500))), &mut model);
+        app.update(Event::SetRegion(generation, Ok(forecast)), &mut model);
+        assert_eq!(model.regions.error, Some(Error::Status(500)));
+        assert_eq!(model.regions.failures.count, 1);
+
+        // until the list is fetched too
+        let regions: RegionsResponse =
+            serde_json::from_str(include_str!("./fixtures/regions.json")).unwrap();
+        app.update(Event::SetRegions(Ok(regions)), &mut model);
+        assert_eq!(model.regions.error, None);
+        assert_eq!(model.regions.failures.count, 0);
+    }
+
+    #[test]
+    fn history_for_past_30_days() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        // request the past 30 days and resolve the time request
+        let update = app.update(Event::GetHistory(30), &mut model);
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        // check the range is split into requests of no more than 14 days
+        let update = app.update(update.events[0].clone(), &mut model);
+        let mut requests: Vec<_> = update
+            .into_effects()
+            .filter_map(Effect::into_http)
+            .collect();
+        let actual: Vec<_> = requests.iter().map(|r| r.operation.url.clone()).collect();
+        let expected = vec![
+            "https://api.carbonintensity.org.uk/intensity/2023-06-06T20:30Z/2023-06-20T20:30Z",
+            "https://api.carbonintensity.org.uk/intensity/2023-06-20T20:30Z/2023-07-04T20:30Z",
+            "https://api.carbonintensity.org.uk/intensity/2023-07-04T20:30Z/2023-07-06T20:30Z",
+        ];
+        assert_eq!(actual, expected);
+
+        // resolve the last request with a simulated response, and the others
+        // with no data
+        let simulated_response: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+        let mut last = requests.pop().unwrap();
+        for mut request in requests {
+            let response = HttpResponse::ok().body(r#"{ "data": [] }"#).build();
+            let update = app.resolve(&mut request, response).unwrap();
+            for event in update.events {
+                app.update(event, &mut model);
+            }
+        }
+        let response = HttpResponse::ok().json(&simulated_response).build();
+        let update = app.resolve(&mut last, response).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        let view = app.view(&model);
+        assert_eq!(view.history_name, "Past 30 days");
+        insta::assert_yaml_snapshot!(view.history_intensity, @r###"
+        ---
+        - date: "2023-07-04T23:30:00+00:00"
+          forecast: 142
+          actual: 129
+          derived: ~
+          mix_quality: Missing
+          uncertain: false
+          label: "00:30"
+          starts_day: false
+          now: false
+        - date: "2023-07-05T00:00:00+00:00"
+          forecast: 136
+          actual: 122
+          derived: ~
+          mix_quality: Missing
+          uncertain: false
+          label: "01:00"
+          starts_day: false
+          now: false
+        "###);
+    }
+
+    #[test]
+    fn history_with_a_failed_chunk() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        app.update(Event::GetHistory(30), &mut model);
+        let update = app.update(
+            Event::SetTimeHistory(TimeResponse("2023-07-06T20:30:00Z".to_string())),
+            &mut model,
+        );
+        assert_effect!(update, Effect::Http(_));
+        let range = model.history.scope.range.clone().unwrap();
+        let generation = model.history.generation;
+        let national: NationalResponse =
+            serde_json::from_str(include_str!("./fixtures/national_intensity.json")).unwrap();
+
+        // the first chunk fails but the others succeed
+        app.update(
+            Event::SetHistory(range.clone(), generation, Err(Error::Status(500))),
+            &mut model,
+        );
+        app.update(
+            Event::SetHistory(range.clone(), generation, Ok(national.clone())),
+            &mut model,
+        );
+        app.update(
+            Event::SetHistory(range, generation, Ok(national)),
+            &mut model,
+        );
+
+        // so the gaps are reported and we'll try again after backing off
+        assert!(!model.history.is_loading());
+        assert_eq!(model.history.error, Some(Error::Status(500)));
+        assert_eq!(model.history.failures.count, 1);
+        assert_eq!(model.history.last_updated, DateTime::<Utc>::default());
+    }
+
+    #[test]
+    fn history_for_date() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::GetHistoryForDate("2023-07-04".to_string()),
+            &mut model,
+        );
+        let request = &mut update.into_effects().find_map(Effect::into_time).unwrap();
+        let response = TimeResponse("2023-07-06T20:30:00Z".to_string());
+        let update = app.resolve(request, response).unwrap();
+
+        let update = app.update(update.events[0].clone(), &mut model);
+        let request = update.into_effects().find_map(Effect::into_http).unwrap();
+        let actual = &request.operation;
+        let expected =
+            &HttpRequest::get("https://api.carbonintensity.org.uk/intensity/date/2023-07-04")
+                .build();
+        assert_eq!(actual, expected);
+
+        // an invalid date is reported
+        let update = app.update(Event::GetHistoryForDate("4th July".to_string()), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(
+            app.view(&model).history_error,
+            Some("\"4th July\" is not a valid date".to_string())
+        );
+        // as is a number of days we won't fetch, without asking for the time
+        let update = app.update(Event::GetHistory(0), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(update.effects.len(), 1);
+        assert_eq!(
+            app.view(&model).history_error,
+            Some("Can't show 0 days of history (1 to 30)".to_string())
+        );
+    }
 }
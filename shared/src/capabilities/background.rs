@@ -0,0 +1,44 @@
+//! Background refresh for Crux apps
+//!
+//! The shell's background task (e.g. a `BGAppRefreshTask` or a `WorkManager`
+//! worker) starts a refresh with an event, and the core tells it when it's
+//! finished, so that the task can be completed and the next one scheduled.
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_macros::Capability;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshComplete {
+    /// Whether any of the data is different from before the refresh
+    pub changed: bool,
+    /// When the next refresh is due (RFC 3339)
+    pub next_refresh: String,
+}
+
+impl Operation for RefreshComplete {
+    type Output = ();
+}
+
+#[derive(Capability)]
+pub struct Background<Ev> {
+    context: CapabilityContext<RefreshComplete, Ev>,
+}
+
+impl<Ev> Background<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<RefreshComplete, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// There's nothing to wait for, so the shell doesn't respond
+    pub fn complete(&self, result: RefreshComplete) {
+        self.context.spawn({
+            let context = self.context.clone();
+            async move {
+                context.notify_shell(result).await;
+            }
+        });
+    }
+}
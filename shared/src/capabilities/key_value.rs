@@ -0,0 +1,73 @@
+//! Key-value storage for Crux apps
+//!
+//! Values are opaque bytes, stored by the shell (e.g. in `UserDefaults` or
+//! `SharedPreferences`) under a string key, so that they survive a restart.
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_macros::Capability;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyValueRequest {
+    Read(String),
+    Write(String, Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyValueResponse {
+    /// The stored value, if there is one
+    Read(Option<Vec<u8>>),
+    /// Whether the value was written
+    Write(bool),
+}
+
+impl Operation for KeyValueRequest {
+    type Output = KeyValueResponse;
+}
+
+#[derive(Capability)]
+pub struct KeyValue<Ev> {
+    context: CapabilityContext<KeyValueRequest, Ev>,
+}
+
+impl<Ev> KeyValue<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<KeyValueRequest, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Read the value stored under `key`, which will be passed to the app
+    /// wrapped in the event produced by the `callback`.
+    pub fn read<F>(&self, key: &str, callback: F)
+    where
+        F: Fn(Option<Vec<u8>>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let key = key.to_string();
+            async move {
+                let value = match context.request_from_shell(KeyValueRequest::Read(key)).await {
+                    KeyValueResponse::Read(value) => value,
+                    KeyValueResponse::Write(_) => None,
+                };
+
+                context.update_app(callback(value));
+            }
+        });
+    }
+
+    /// Store `value` under `key`, replacing any existing value. There's
+    /// nothing useful the app can do if this fails, so there's no callback.
+    pub fn write(&self, key: &str, value: Vec<u8>) {
+        self.context.spawn({
+            let context = self.context.clone();
+            let key = key.to_string();
+            async move {
+                context
+                    .request_from_shell(KeyValueRequest::Write(key, value))
+                    .await;
+            }
+        });
+    }
+}
@@ -1,2 +1,5 @@
+pub mod background;
+pub mod key_value;
 pub mod location;
+pub mod notification;
 pub mod time;
@@ -0,0 +1,63 @@
+//! Local notifications for Crux apps
+//!
+//! The shell schedules each notification with the platform (e.g.
+//! `UNUserNotificationCenter` or `WorkManager`), so that it's shown at the
+//! given time even if the app isn't running.
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_macros::Capability;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Identifies the notification, so that it can be cancelled. Scheduling
+    /// another with the same id replaces it.
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    /// When to show the notification (RFC 3339). If this has passed, it
+    /// should be shown straight away.
+    pub at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationRequest {
+    Schedule(Notification),
+    Cancel(String),
+}
+
+impl Operation for NotificationRequest {
+    type Output = ();
+}
+
+#[derive(Capability)]
+pub struct Notify<Ev> {
+    context: CapabilityContext<NotificationRequest, Ev>,
+}
+
+impl<Ev> Notify<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<NotificationRequest, Ev>) -> Self {
+        Self { context }
+    }
+
+    pub fn schedule(&self, notification: Notification) {
+        self.request(NotificationRequest::Schedule(notification));
+    }
+
+    /// Cancel a scheduled notification, if it hasn't been shown yet
+    pub fn cancel(&self, id: &str) {
+        self.request(NotificationRequest::Cancel(id.to_string()));
+    }
+
+    /// There's nothing to wait for, so the shell doesn't respond
+    fn request(&self, request: NotificationRequest) {
+        self.context.spawn({
+            let context = self.context.clone();
+            async move {
+                context.notify_shell(request).await;
+            }
+        });
+    }
+}
@@ -9,7 +9,15 @@ use wasm_bindgen::prelude::wasm_bindgen;
 pub use crux_core::{bridge::Bridge, Core, Request};
 
 pub use app::*;
-pub use model::{Mode, Scope};
+pub use model::{
+    freshness::Freshness,
+    fuel::Fuel,
+    horizon::Horizon,
+    intensity::{IntensityIndex, MixQuality},
+    thresholds::Condition,
+    zone::Zone,
+    Mode, Scope,
+};
 
 // TODO hide this plumbing
 
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::intensity::Period;
+
+/// How far a period's forecast was from what actually happened
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeriodError {
+    pub from: DateTime<Utc>,
+    pub forecast: i32,
+    pub actual: i32,
+    /// Forecast minus actual, so positive when the forecast was too high
+    pub error: i32,
+    /// The error as a percentage of the actual, if the actual wasn't zero
+    pub percentage_error: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Accuracy {
+    pub periods: Vec<PeriodError>,
+    pub mean_absolute_error: f64,
+    /// The mean error, so positive when forecasts tend to be too high
+    pub bias: f64,
+    pub mean_absolute_percentage_error: Option<f64>,
+}
+
+impl PeriodError {
+    pub fn new(period: &Period) -> Option<Self> {
+        let intensity = period.intensity.as_ref()?;
+        let actual = intensity.actual?;
+        let error = intensity.forecast - actual;
+
+        Some(Self {
+            from: period.from,
+            forecast: intensity.forecast,
+            actual,
+            error,
+            percentage_error: (actual != 0).then(|| f64::from(error) / f64::from(actual) * 100.0),
+        })
+    }
+}
+
+/// Compares forecasts with actuals, for the periods that have both. Only
+/// national data has actuals; regional data is forecast only, so has no
+/// accuracy.
+pub fn accuracy(periods: &[Period]) -> Option<Accuracy> {
+    let periods: Vec<PeriodError> = periods.iter().filter_map(PeriodError::new).collect();
+    if periods.is_empty() {
+        return None;
+    }
+
+    let count = periods.len() as f64;
+    let mean_absolute_error = periods
+        .iter()
+        .map(|p| f64::from(p.error.abs()))
+        .sum::<f64>()
+        / count;
+    let bias = periods.iter().map(|p| f64::from(p.error)).sum::<f64>() / count;
+
+    let percentages: Vec<f64> = periods
+        .iter()
+        .filter_map(|p| p.percentage_error)
+        .map(f64::abs)
+        .collect();
+    let mean_absolute_percentage_error = (!percentages.is_empty())
+        .then(|| percentages.iter().sum::<f64>() / percentages.len() as f64);
+
+    Some(Accuracy {
+        periods,
+        mean_absolute_error,
+        bias,
+        mean_absolute_percentage_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity;
+    use chrono::TimeZone;
+
+    fn periods(values: &[(i32, Option<i32>)]) -> Vec<Period> {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let forecasts: Vec<i32> = values.iter().map(|&(forecast, _)| forecast).collect();
+        let mut periods = intensity::periods(start, &forecasts);
+        for (period, &(_, actual)) in periods.iter_mut().zip(values) {
+            period.intensity.as_mut().unwrap().actual = actual;
+        }
+        periods
+    }
+
+    #[test]
+    fn aggregates_over_periods_with_actuals() {
+        let periods = periods(&[
+            (110, Some(100)),
+            (90, Some(100)),
+            (130, Some(100)),
+            (50, None),
+        ]);
+
+        let accuracy = accuracy(&periods).unwrap();
+
+        assert_eq!(accuracy.periods.len(), 3);
+        assert_eq!(accuracy.mean_absolute_error, 50.0 / 3.0);
+        assert_eq!(accuracy.bias, 10.0);
+        assert_eq!(accuracy.mean_absolute_percentage_error, Some(50.0 / 3.0));
+    }
+
+    #[test]
+    fn per_period_error() {
+        let periods = periods(&[(142, Some(129)), (10, Some(0))]);
+
+        let accuracy = accuracy(&periods).unwrap();
+
+        assert_eq!(accuracy.periods[0].error, 13);
+        assert_eq!(
+            accuracy.periods[0].percentage_error,
+            Some(13.0 / 129.0 * 100.0)
+        );
+        assert_eq!(accuracy.periods[1].error, 10);
+        assert_eq!(accuracy.periods[1].percentage_error, None);
+        assert_eq!(
+            accuracy.mean_absolute_percentage_error,
+            Some(13.0 / 129.0 * 100.0)
+        );
+    }
+
+    #[test]
+    fn no_accuracy_without_actuals() {
+        assert_eq!(accuracy(&periods(&[(121, None), (116, None)])), None);
+        assert_eq!(accuracy(&[]), None);
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::intensity::Period;
+
+/// Two forecasts for the same period, e.g. local and national
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Difference {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub first: i32,
+    pub second: i32,
+}
+
+impl Difference {
+    /// First minus second (gCO2/kWh), so negative when the first is cleaner
+    pub fn difference(&self) -> i32 {
+        self.first - self.second
+    }
+
+    /// How much cleaner (or dirtier, if negative) the first is than the
+    /// second, as a percentage of the second
+    pub fn cleaner_percent(&self) -> Option<f64> {
+        (self.second != 0)
+            .then(|| f64::from(self.second - self.first) / f64::from(self.second) * 100.0)
+    }
+}
+
+/// Pairs up the periods of two forecasts that start at the same time and
+/// both have a forecast
+pub fn compare(first: &[Period], second: &[Period]) -> Vec<Difference> {
+    first
+        .iter()
+        .filter_map(|period| {
+            let first = period.intensity.as_ref()?;
+            let second = second
+                .iter()
+                .find(|p| p.from == period.from)?
+                .intensity
+                .as_ref()?;
+            Some(Difference {
+                from: period.from,
+                to: period.to,
+                first: first.forecast,
+                second: second.forecast,
+            })
+        })
+        .collect()
+}
+
+/// The running total, period by period, of how many more grams of CO2 a
+/// constant load (kW) emits under the first forecast than the second, so
+/// negative when the first is cleaner
+pub fn cumulative_grams(differences: &[Difference], load_kw: f64) -> Vec<f64> {
+    differences
+        .iter()
+        .scan(0.0, |total, difference| {
+            let hours = (difference.to - difference.from).num_seconds() as f64 / 3600.0;
+            *total += f64::from(difference.difference()) * load_kw * hours;
+            Some(*total)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::periods;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn align_periods_by_start() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let local = periods(start, &[90, 120, 150]);
+        let national = periods(start + Duration::minutes(30), &[100, 200, 300]);
+
+        let differences = compare(&local, &national);
+
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].from, start + Duration::minutes(30));
+        assert_eq!(differences[0].difference(), 20);
+        assert_eq!(differences[0].cleaner_percent(), Some(-20.0));
+        assert_eq!(differences[1].difference(), -50);
+        assert_eq!(differences[1].cleaner_percent(), Some(25.0));
+    }
+
+    #[test]
+    fn cumulative_difference_for_load() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let first = periods(start, &[100, 250, 200]);
+        let second = periods(start, &[200, 150, 200]);
+
+        let differences = compare(&first, &second);
+
+        // 2 kW for half an hour is 1 kWh
+        assert_eq!(cumulative_grams(&differences, 2.0), vec![-100.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The request couldn't be made, or no response was received
+    Network(String),
+    /// The server responded with a non-success status code
+    Status(u16),
+    /// The response body wasn't what we expected
+    Decode(String),
+    /// There is no postcode near the device's location
+    NoPostcode,
+    /// The postcode or outcode entered doesn't exist
+    InvalidPostcode(String),
+    /// The date entered isn't a valid date (YYYY-MM-DD) or time (RFC 3339)
+    InvalidDate(String),
+    /// The region ID entered isn't one of the DNO regions (1 to 14)
+    InvalidRegion(String),
+    /// The energy entered is negative or not a number
+    InvalidEnergy(String),
+    /// The number of days of history asked for isn't 1 to 30
+    InvalidDays(u32),
+    /// A threshold's warning (in minutes) is negative or more than a day
+    InvalidWarning(i64),
+    /// A job's duration (in minutes) is zero, negative, or longer than the
+    /// forecast
+    InvalidDuration(i64),
+    /// A job's deadline is too soon for it to finish by, even if it starts now
+    DeadlineTooSoon(String),
+    /// The user didn't allow access to their location
+    LocationDenied,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network(message) => write!(f, "Network error: {message}"),
+            Error::Status(status) => write!(f, "Server responded with status {status}"),
+            Error::Decode(message) => write!(f, "Unexpected response: {message}"),
+            Error::NoPostcode => write!(f, "Could not find a postcode for your location"),
+            Error::InvalidPostcode(code) => write!(f, "\"{code}\" is not a valid postcode"),
+            Error::InvalidDate(date) => write!(f, "\"{date}\" is not a valid date"),
+            Error::InvalidRegion(id) => write!(f, "\"{id}\" is not a region ID (1 to 14)"),
+            Error::InvalidEnergy(energy) => {
+                write!(f, "\"{energy}\" is not a valid amount of energy")
+            }
+            Error::InvalidDays(days) => write!(f, "Can't show {days} days of history (1 to 30)"),
+            Error::InvalidWarning(minutes) => {
+                write!(f, "Can't warn {minutes} minutes before (0 to 1440)")
+            }
+            Error::InvalidDuration(minutes) => {
+                write!(f, "Can't schedule a job lasting {minutes} minutes")
+            }
+            Error::DeadlineTooSoon(deadline) => {
+                write!(f, "The job can't finish by \"{deadline}\"")
+            }
+            Error::LocationDenied => write!(f, "Location access was denied"),
+        }
+    }
+}
+
+/// Checks the status of a raw HTTP response and decodes its JSON body.
+///
+/// We don't use `expect_json` for this because, on failure, it discards the
+/// status code of the response.
+pub fn decode<T>(response: crux_http::Result<crux_http::Response<Vec<u8>>>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let mut response = response.map_err(|e| Error::Network(format!("{e:?}")))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Error::Status(status.into()));
+    }
+
+    let body = response
+        .take_body()
+        .ok_or_else(|| Error::Network("response had no body".to_string()))?;
+    serde_json::from_slice(&body).map_err(|e| Error::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use crux_http::{http::StatusCode, testing::ResponseBuilder};
+
+    #[test]
+    fn decode_success() {
+        let response = ResponseBuilder::ok().body(b"[1, 2]".to_vec()).build();
+
+        assert_eq!(decode::<Vec<i32>>(Ok(response)), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn decode_failed_request() {
+        let error = crux_http::Error::new(None, "connection refused");
+
+        assert_matches!(decode::<Vec<i32>>(Err(error)), Err(Error::Network(_)));
+    }
+
+    #[test]
+    fn decode_status() {
+        let response = ResponseBuilder::with_status(StatusCode::NotFound)
+            .body(b"Not Found".to_vec())
+            .build();
+
+        assert_eq!(decode::<Vec<i32>>(Ok(response)), Err(Error::Status(404)));
+    }
+
+    #[test]
+    fn decode_unexpected_body() {
+        let response = ResponseBuilder::ok().body(b"<html>".to_vec()).build();
+
+        assert_matches!(decode::<Vec<i32>>(Ok(response)), Err(Error::Decode(_)));
+    }
+}
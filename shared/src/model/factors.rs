@@ -1,4 +1,15 @@
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{
+    fuel::Fuel,
+    intensity::{GenerationMix, INTENSITY_API},
+};
+
+pub(crate) fn url() -> Url {
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join("/intensity/factors").unwrap()
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Root {
@@ -37,7 +48,6 @@ pub struct Factors {
     pub wind: i64,
 }
 
-#[allow(dead_code)]
 impl Factors {
     pub fn new() -> Self {
         Self {
@@ -57,4 +67,92 @@ impl Factors {
             wind: 0,
         }
     }
+
+    /// The emission factor (gCO2/kWh) for a fuel in a generation mix. The mix
+    /// doesn't say where imports come from, so we use the mean of the
+    /// interconnector factors, and all gas is assumed to be combined cycle.
+    pub fn factor(&self, fuel: &Fuel) -> Option<f64> {
+        let factor = match fuel {
+            Fuel::Biomass => self.biomass,
+            Fuel::Coal => self.coal,
+            Fuel::Imports => {
+                return Some(
+                    (self.dutch_imports + self.french_imports + self.irish_imports) as f64 / 3.0,
+                )
+            }
+            Fuel::Gas => self.gas_combined_cycle,
+            Fuel::Hydro => self.hydro,
+            Fuel::Nuclear => self.nuclear,
+            Fuel::Oil => self.oil,
+            Fuel::Other => self.other,
+            Fuel::Solar => self.solar,
+            Fuel::Wind => self.wind,
+            Fuel::Unknown(_) => return None,
+        };
+        Some(factor as f64)
+    }
+
+    /// How much a fuel contributes (gCO2/kWh) to the intensity of a mix
+    pub fn contribution(&self, mix: &GenerationMix) -> f64 {
+        self.factor(&mix.fuel).unwrap_or_default() * f64::from(mix.percentage) / 100.0
+    }
+
+    /// The intensity (gCO2/kWh) of a generation mix, derived from the
+    /// emission factor of each fuel
+    pub fn intensity(&self, mix: &[GenerationMix]) -> f64 {
+        mix.iter().map(|mix| self.contribution(mix)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::ApiName;
+
+    fn mix(fuel: &str, percentage: f32) -> GenerationMix {
+        GenerationMix {
+            fuel: Fuel::from_name(fuel),
+            percentage,
+        }
+    }
+
+    #[test]
+    fn contribution_of_each_fuel() {
+        let factors = Factors::new();
+
+        assert_eq!(factors.contribution(&mix("gas", 50.0)), 197.0);
+        assert_eq!(factors.contribution(&mix("wind", 50.0)), 0.0);
+        assert_eq!(factors.contribution(&mix("unobtainium", 10.0)), 0.0);
+    }
+
+    #[test]
+    fn imports_use_mean_of_interconnectors() {
+        assert_eq!(Factors::new().factor(&Fuel::Imports), Some(985.0 / 3.0));
+    }
+
+    #[test]
+    fn intensity_of_mix() {
+        let factors = Factors::new();
+        let mix = vec![
+            mix("biomass", 5.0),
+            mix("coal", 1.0),
+            mix("gas", 40.0),
+            mix("nuclear", 15.0),
+            mix("wind", 39.0),
+        ];
+
+        assert_eq!(factors.intensity(&mix), 6.0 + 9.37 + 157.6);
+    }
+
+    #[test]
+    fn deserialize_api_response() {
+        let json = r#"{ "data": [{ "Biomass": 120, "Coal": 937, "Dutch Imports": 474,
+            "French Imports": 53, "Gas (Combined Cycle)": 394, "Gas (Open Cycle)": 651,
+            "Hydro": 0, "Irish Imports": 458, "Nuclear": 0, "Oil": 935, "Other": 300,
+            "Pumped Storage": 0, "Solar": 0, "Wind": 0 }] }"#;
+
+        let root: Root = serde_json::from_str(json).unwrap();
+
+        assert_eq!(root.data, vec![Factors::new()]);
+    }
 }
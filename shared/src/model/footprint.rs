@@ -0,0 +1,268 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{error::Error, intensity::Period, zone::Zone};
+
+/// Energy used over a time window, e.g. 7 kWh charging a car between 01:00
+/// and 04:00. The energy is assumed to be used evenly across the window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Usage {
+    pub energy_kwh: f64,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl Usage {
+    /// Usage between two RFC 3339 times. The energy can't be negative, and
+    /// the usage has to end after it starts.
+    pub fn parse(energy_kwh: f64, from: &str, to: &str) -> Result<Self, Error> {
+        if !energy_kwh.is_finite() || energy_kwh < 0.0 {
+            return Err(Error::InvalidEnergy(energy_kwh.to_string()));
+        }
+        let parse = |time: &str| {
+            DateTime::parse_from_rfc3339(time.trim())
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| Error::InvalidDate(time.to_string()))
+        };
+        let usage = Usage {
+            energy_kwh,
+            from: parse(from)?,
+            to: parse(to)?,
+        };
+        if usage.to <= usage.from {
+            return Err(Error::InvalidDate(to.to_string()));
+        }
+        Ok(usage)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Emissions {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Grams of CO2 from using the energy in this window
+    pub grams: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Footprint {
+    pub usage: Emissions,
+    /// The window of the same length, on the same day, with the lowest emissions
+    pub best: Emissions,
+    /// The window of the same length, on the same day, with the highest emissions
+    pub worst: Emissions,
+}
+
+/// Calculates the emissions for some energy usage, and what they would have
+/// been in the best and worst windows of the same length on the same local
+/// day. Returns `None` if the periods don't cover the whole usage window.
+///
+/// Other windows start on a period boundary and must be covered by the
+/// periods too. Ties go to the usage window, then to the earliest window.
+pub fn footprint(periods: &[Period], usage: &Usage, zone: Zone) -> Option<Footprint> {
+    let emissions = |from, to| {
+        average_intensity(periods, from, to).map(|intensity| Emissions {
+            from,
+            to,
+            grams: intensity * usage.energy_kwh,
+        })
+    };
+
+    let duration = usage.to - usage.from;
+    let actual = emissions(usage.from, usage.to)?;
+
+    let day = zone.day(zone.date(usage.from));
+    let mut best = actual.clone();
+    let mut worst = actual.clone();
+    for window in periods
+        .iter()
+        .map(|period| period.from)
+        .filter(|&from| from >= day.from && from + duration <= day.to)
+        .filter_map(|from| emissions(from, from + duration))
+    {
+        if window.grams < best.grams {
+            best = window.clone();
+        }
+        if window.grams > worst.grams {
+            worst = window;
+        }
+    }
+
+    Some(Footprint {
+        usage: actual,
+        best,
+        worst,
+    })
+}
+
+/// The average intensity (gCO2/kWh) between two times, weighting each
+/// period by how much of it overlaps, so the times don't need to be on the
+/// half hour. Uses the actual intensity where there is one, otherwise the
+/// forecast.
+fn average_intensity(periods: &[Period], from: DateTime<Utc>, to: DateTime<Utc>) -> Option<f64> {
+    let duration = (to - from).num_seconds();
+    if duration <= 0 {
+        return None;
+    }
+
+    let mut covered = 0;
+    let mut total = 0.0;
+    for period in periods {
+        let overlap = (period.to.min(to) - period.from.max(from)).num_seconds();
+        if overlap <= 0 {
+            continue;
+        }
+        let intensity = period.intensity.as_ref()?;
+        let intensity = intensity.actual.unwrap_or(intensity.forecast);
+        covered += overlap;
+        total += f64::from(intensity) * overlap as f64;
+    }
+
+    (covered == duration).then_some(total / duration as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::periods;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn parse_usage() {
+        let usage = Usage::parse(7.0, "2023-07-05T00:00:00Z", "2023-07-05T03:00:00+00:00");
+        assert_eq!(
+            usage.map(|usage| (usage.to - usage.from).num_hours()),
+            Ok(3)
+        );
+        assert_eq!(
+            Usage::parse(f64::NAN, "2023-07-05T00:00:00Z", "2023-07-05T03:00:00Z"),
+            Err(Error::InvalidEnergy("NaN".to_string()))
+        );
+        assert_eq!(
+            Usage::parse(7.0, "01:00", "2023-07-05T03:00:00Z"),
+            Err(Error::InvalidDate("01:00".to_string()))
+        );
+        assert_eq!(
+            Usage::parse(7.0, "2023-07-05T03:00:00Z", "2023-07-05T03:00:00Z"),
+            Err(Error::InvalidDate("2023-07-05T03:00:00Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn interpolates_across_period_boundaries() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 1, 0, 0).unwrap();
+        let periods = periods(start, &[100, 200, 400]);
+        let usage = Usage {
+            energy_kwh: 2.0,
+            from: start + Duration::minutes(15),
+            to: start + Duration::minutes(75),
+        };
+
+        let footprint = footprint(&periods, &usage, Zone::Utc).unwrap();
+
+        // 15 minutes at 100, 30 at 200 and 15 at 400
+        assert_eq!(footprint.usage.grams, 2.0 * 225.0);
+    }
+
+    #[test]
+    fn prefers_actual_intensity() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 1, 0, 0).unwrap();
+        let mut periods = periods(start, &[100]);
+        periods[0].intensity.as_mut().unwrap().actual = Some(150);
+        let usage = Usage {
+            energy_kwh: 1.0,
+            from: start,
+            to: start + Duration::minutes(30),
+        };
+
+        assert_eq!(
+            footprint(&periods, &usage, Zone::Utc).unwrap().usage.grams,
+            150.0
+        );
+    }
+
+    #[test]
+    fn best_and_worst_windows_on_the_same_day() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 22, 0, 0).unwrap();
+        let periods = periods(
+            start,
+            &[
+                200, 100, 300, 250, // the next day
+                10, 10,
+            ],
+        );
+        let usage = Usage {
+            energy_kwh: 7.0,
+            from: start + Duration::minutes(30),
+            to: start + Duration::minutes(90),
+        };
+
+        let footprint = footprint(&periods, &usage, Zone::Utc).unwrap();
+
+        assert_eq!(footprint.usage.grams, 7.0 * 200.0);
+        assert_eq!(
+            footprint.best,
+            Emissions {
+                from: start,
+                to: start + Duration::hours(1),
+                grams: 7.0 * 150.0,
+            }
+        );
+        assert_eq!(
+            footprint.worst,
+            Emissions {
+                from: start + Duration::hours(1),
+                to: start + Duration::hours(2),
+                grams: 7.0 * 275.0,
+            }
+        );
+    }
+
+    #[test]
+    fn best_and_worst_windows_on_the_same_local_day() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 22, 0, 0).unwrap();
+        let periods = periods(
+            start,
+            &[
+                200, 100, // midnight BST
+                300, 250, 10, 10,
+            ],
+        );
+        let usage = Usage {
+            energy_kwh: 7.0,
+            from: start + Duration::minutes(90),
+            to: start + Duration::minutes(150),
+        };
+
+        // in UTC the next day hasn't started, so the usage is the best window
+        let utc = footprint(&periods, &usage, Zone::Utc).unwrap();
+        assert_eq!(utc.best, utc.usage);
+        assert_eq!(utc.worst.from, start + Duration::hours(1));
+
+        let uk = footprint(&periods, &usage, Zone::UnitedKingdom).unwrap();
+        assert_eq!(uk.best.from, start + Duration::hours(2));
+        assert_eq!(uk.best.grams, 7.0 * 10.0);
+        assert_eq!(uk.worst.from, start + Duration::hours(1));
+    }
+
+    #[test]
+    fn no_footprint_unless_usage_is_covered() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 1, 0, 0).unwrap();
+        let mut periods = periods(start, &[100, 200]);
+        let usage = Usage {
+            energy_kwh: 1.0,
+            from: start,
+            to: start + Duration::minutes(90),
+        };
+
+        assert_eq!(footprint(&periods, &usage, Zone::Utc), None);
+
+        periods[1].intensity = None;
+        let usage = Usage {
+            to: start + Duration::minutes(60),
+            ..usage
+        };
+
+        assert_eq!(footprint(&periods, &usage, Zone::Utc), None);
+    }
+}
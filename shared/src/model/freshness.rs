@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How up to date a set of data is, so that the UI can tell the user if
+/// they're looking at old data (e.g. because they're offline)
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Freshness {
+    #[default]
+    NeverLoaded,
+    Fresh,
+    Stale,
+    Expired,
+}
+
+impl Freshness {
+    /// Data goes stale once it's older than `stale_after`, i.e. once it's
+    /// due to be fetched again, and expires once it's older than
+    /// `expired_after`, i.e. once the forecast is all in the past
+    pub fn new(
+        last_updated: DateTime<Utc>,
+        now: DateTime<Utc>,
+        stale_after: Duration,
+        expired_after: Duration,
+    ) -> Self {
+        if last_updated == DateTime::<Utc>::default() {
+            return Freshness::NeverLoaded;
+        }
+
+        let age = now - last_updated;
+        if age > expired_after {
+            Freshness::Expired
+        } else if age > stale_after {
+            Freshness::Stale
+        } else {
+            Freshness::Fresh
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn freshness_by_age() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 6, 20, 30, 0).unwrap();
+        let stale_after = Duration::minutes(30);
+        let expired_after = Duration::hours(24);
+
+        assert_eq!(
+            Freshness::new(DateTime::default(), now, stale_after, expired_after),
+            Freshness::NeverLoaded
+        );
+        assert_eq!(
+            Freshness::new(now, now, stale_after, expired_after),
+            Freshness::Fresh
+        );
+        assert_eq!(
+            Freshness::new(now - Duration::minutes(30), now, stale_after, expired_after),
+            Freshness::Fresh
+        );
+        assert_eq!(
+            Freshness::new(now - Duration::minutes(31), now, stale_after, expired_after),
+            Freshness::Stale
+        );
+        assert_eq!(
+            Freshness::new(
+                now - Duration::minutes(11),
+                now,
+                Duration::minutes(10),
+                expired_after
+            ),
+            Freshness::Stale
+        );
+        assert_eq!(
+            Freshness::new(now - Duration::days(5), now, stale_after, expired_after),
+            Freshness::Expired
+        );
+        assert_eq!(
+            Freshness::new(now - Duration::hours(25), now, stale_after, expired_after),
+            Freshness::Expired
+        );
+        assert_eq!(
+            Freshness::new(
+                now - Duration::hours(25),
+                now,
+                stale_after,
+                Duration::hours(48)
+            ),
+            Freshness::Stale
+        );
+    }
+}
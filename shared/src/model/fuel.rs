@@ -0,0 +1,200 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::intensity::{ApiName, GenerationMix};
+
+/// A fuel in the generation mix, in the order we show them (most carbon
+/// intensive first)
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fuel {
+    Coal,
+    Oil,
+    Gas,
+    Imports,
+    Biomass,
+    Nuclear,
+    Hydro,
+    Wind,
+    Solar,
+    #[default]
+    Other,
+    /// A fuel we don't know about, named as the API names it
+    Unknown(String),
+}
+
+impl Fuel {
+    /// Whether the API counts the fuel as renewable
+    pub fn is_renewable(&self) -> bool {
+        matches!(self, Fuel::Biomass | Fuel::Hydro | Fuel::Solar | Fuel::Wind)
+    }
+
+    /// Renewables and nuclear
+    pub fn is_low_carbon(&self) -> bool {
+        self.is_renewable() || *self == Fuel::Nuclear
+    }
+
+    pub fn is_fossil(&self) -> bool {
+        matches!(self, Fuel::Coal | Fuel::Gas | Fuel::Oil)
+    }
+}
+
+impl ApiName for Fuel {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "biomass" => Fuel::Biomass,
+            "coal" => Fuel::Coal,
+            "imports" => Fuel::Imports,
+            "gas" => Fuel::Gas,
+            "nuclear" => Fuel::Nuclear,
+            "oil" => Fuel::Oil,
+            "other" => Fuel::Other,
+            "hydro" => Fuel::Hydro,
+            "solar" => Fuel::Solar,
+            "wind" => Fuel::Wind,
+            _ => Fuel::Unknown(name.to_string()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Fuel::Biomass => "biomass",
+            Fuel::Coal => "coal",
+            Fuel::Imports => "imports",
+            Fuel::Gas => "gas",
+            Fuel::Nuclear => "nuclear",
+            Fuel::Oil => "oil",
+            Fuel::Other => "other",
+            Fuel::Hydro => "hydro",
+            Fuel::Solar => "solar",
+            Fuel::Wind => "wind",
+            Fuel::Unknown(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Fuel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Fuel::Biomass => "Biomass",
+            Fuel::Coal => "Coal",
+            Fuel::Imports => "Imports",
+            Fuel::Gas => "Gas",
+            Fuel::Nuclear => "Nuclear",
+            Fuel::Oil => "Oil",
+            Fuel::Other => "Other",
+            Fuel::Hydro => "Hydro",
+            Fuel::Solar => "Solar",
+            Fuel::Wind => "Wind",
+            Fuel::Unknown(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+/// A generation mix added up into broad categories of fuel (percentages).
+/// Low carbon includes the renewables, so the categories overlap.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Categories {
+    pub renewable: f64,
+    pub low_carbon: f64,
+    pub fossil: f64,
+    pub imports: f64,
+    /// Fuels that aren't in any of the other categories
+    pub other: f64,
+}
+
+impl Categories {
+    pub fn new(mix: &[GenerationMix]) -> Self {
+        let share = |category: fn(&Fuel) -> bool| -> f64 {
+            mix.iter()
+                .filter(|mix| category(&mix.fuel))
+                .map(|mix| f64::from(mix.percentage))
+                .sum()
+        };
+        let total: f64 = mix.iter().map(|mix| f64::from(mix.percentage)).sum();
+        let low_carbon = share(Fuel::is_low_carbon);
+        let fossil = share(Fuel::is_fossil);
+        let imports = share(|fuel| *fuel == Fuel::Imports);
+
+        Self {
+            renewable: share(Fuel::is_renewable),
+            low_carbon,
+            fossil,
+            imports,
+            other: total - low_carbon - fossil - imports,
+        }
+    }
+
+    /// The mean of each category across several mixes
+    pub fn mean(categories: &[Categories]) -> Option<Self> {
+        if categories.is_empty() {
+            return None;
+        }
+        let count = categories.len() as f64;
+        let mean = |category: fn(&Categories) -> f64| -> f64 {
+            categories.iter().map(category).sum::<f64>() / count
+        };
+
+        Some(Self {
+            renewable: mean(|c| c.renewable),
+            low_carbon: mean(|c| c.low_carbon),
+            fossil: mean(|c| c.fossil),
+            imports: mean(|c| c.imports),
+            other: mean(|c| c.other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(fuels: &[(&str, f32)]) -> Vec<GenerationMix> {
+        fuels
+            .iter()
+            .map(|&(fuel, percentage)| GenerationMix {
+                fuel: Fuel::from_name(fuel),
+                percentage,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn categorise_mix() {
+        let mix = mix(&[
+            ("biomass", 5.0),
+            ("coal", 1.0),
+            ("imports", 8.0),
+            ("gas", 40.0),
+            ("nuclear", 15.0),
+            ("other", 1.0),
+            ("hydro", 0.0),
+            ("solar", 10.0),
+            ("wind", 20.0),
+        ]);
+
+        assert_eq!(
+            Categories::new(&mix),
+            Categories {
+                renewable: 35.0,
+                low_carbon: 50.0,
+                fossil: 41.0,
+                imports: 8.0,
+                other: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn mean_of_categories() {
+        let a = Categories::new(&mix(&[("wind", 60.0), ("gas", 40.0)]));
+        let b = Categories::new(&mix(&[("wind", 20.0), ("gas", 80.0)]));
+
+        let mean = Categories::mean(&[a, b]).unwrap();
+
+        assert_eq!(mean.renewable, 40.0);
+        assert_eq!(mean.fossil, 60.0);
+        assert_eq!(Categories::mean(&[]), None);
+    }
+}
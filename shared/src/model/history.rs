@@ -0,0 +1,159 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{error::Error, intensity::Period, national_intensity};
+
+/// The most days of history we'll fetch at once, which is already three
+/// requests' worth
+pub const MAX_DAYS: u32 = 30;
+
+/// A range of past national intensity, with forecasts and actuals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum HistoryRange {
+    /// The given number of days up to now
+    Days(u32),
+    /// A single (UK) day
+    Date(NaiveDate),
+}
+
+impl HistoryRange {
+    /// The given number of days up to now, as long as there's at least one
+    /// and no more than `MAX_DAYS`
+    pub fn days(days: u32) -> Result<Self, Error> {
+        if (1..=MAX_DAYS).contains(&days) {
+            Ok(HistoryRange::Days(days))
+        } else {
+            Err(Error::InvalidDays(days))
+        }
+    }
+
+    /// The requests needed to fetch the whole range
+    pub fn urls(&self, now: DateTime<Utc>) -> Vec<Url> {
+        match self {
+            HistoryRange::Days(1) => vec![national_intensity::past_url(&now)],
+            HistoryRange::Days(_) => match self.bounds(now) {
+                Some((from, to)) => national_intensity::range_urls(&from, &to),
+                None => vec![],
+            },
+            HistoryRange::Date(date) => vec![national_intensity::date_url(date)],
+        }
+    }
+
+    /// The start and end of a range relative to now. A date's periods are
+    /// whatever the API considers to be in that day (which differs from UTC
+    /// during BST), so it has no bounds of its own.
+    pub fn bounds(&self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        match self {
+            HistoryRange::Days(days) => now
+                .checked_sub_signed(Duration::days(i64::from(*days)))
+                .map(|from| (from, now)),
+            HistoryRange::Date(_) => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            HistoryRange::Days(1) => "Past 24 hours".to_string(),
+            HistoryRange::Days(days) => format!("Past {days} days"),
+            HistoryRange::Date(date) => date.format("%-d %B %Y").to_string(),
+        }
+    }
+}
+
+/// Adds the periods from one of the responses for a range to those we
+/// already have, dropping any that have fallen out of the range
+pub fn merge(
+    periods: &mut Vec<Period>,
+    new: Vec<Period>,
+    bounds: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) {
+    // chunks overlap by a period, so prefer the newer one
+    periods.retain(|period| !new.iter().any(|n| n.from == period.from));
+    periods.extend(new);
+    if let Some((from, to)) = bounds {
+        periods.retain(|period| period.to > from && period.from < to);
+    }
+    periods.sort_by_key(|period| period.from);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::{Intensity, IntensityIndex};
+    use chrono::TimeZone;
+
+    fn period(from: DateTime<Utc>, forecast: i32) -> Period {
+        Period {
+            from,
+            to: from + Duration::minutes(30),
+            intensity: Some(Intensity {
+                forecast,
+                actual: Some(forecast),
+                index: IntensityIndex::Low,
+            }),
+            generation_mix: None,
+        }
+    }
+
+    #[test]
+    fn merge_chunks_in_order() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 6, 12, 0, 0).unwrap();
+        let bounds = HistoryRange::Days(1).bounds(now);
+        let start = now - Duration::hours(2);
+        let mut periods = vec![period(start + Duration::minutes(60), 1)];
+
+        merge(
+            &mut periods,
+            vec![
+                period(start, 2),
+                period(start + Duration::minutes(30), 3),
+                period(start + Duration::minutes(60), 4),
+            ],
+            bounds,
+        );
+
+        let forecasts: Vec<_> = periods
+            .iter()
+            .map(|p| p.intensity.as_ref().unwrap().forecast)
+            .collect();
+        assert_eq!(forecasts, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn merge_drops_periods_out_of_range() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 6, 12, 0, 0).unwrap();
+        let bounds = HistoryRange::Days(7).bounds(now);
+        let mut periods = vec![period(now - Duration::days(8), 1)];
+
+        merge(
+            &mut periods,
+            vec![period(now - Duration::days(1), 2)],
+            bounds,
+        );
+
+        assert_eq!(periods.len(), 1);
+        assert_eq!(periods[0].from, now - Duration::days(1));
+    }
+
+    #[test]
+    fn days_in_range() {
+        assert_eq!(HistoryRange::days(7), Ok(HistoryRange::Days(7)));
+        assert_eq!(HistoryRange::days(30), Ok(HistoryRange::Days(30)));
+        assert_eq!(HistoryRange::days(0), Err(Error::InvalidDays(0)));
+        assert_eq!(
+            HistoryRange::days(u32::MAX),
+            Err(Error::InvalidDays(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(HistoryRange::Days(1).name(), "Past 24 hours");
+        assert_eq!(HistoryRange::Days(30).name(), "Past 30 days");
+        assert_eq!(
+            HistoryRange::Date(NaiveDate::from_ymd_opt(2023, 7, 4).unwrap()).name(),
+            "4 July 2023"
+        );
+    }
+}
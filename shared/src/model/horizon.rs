@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Forecasts further ahead than this are less certain
+pub const CERTAIN_HOURS: i64 = 24;
+
+/// How far ahead to fetch forecasts, set by the shell with
+/// `Event::SetHorizon`
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Horizon {
+    #[default]
+    Hours24,
+    Hours48,
+}
+
+impl Horizon {
+    pub fn hours(self) -> i64 {
+        match self {
+            Horizon::Hours24 => 24,
+            Horizon::Hours48 => 48,
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        Duration::hours(self.hours())
+    }
+
+    /// The API's name for a forecast of this length, e.g. "fw24h"
+    pub fn forward(self) -> String {
+        format!("fw{}h", self.hours())
+    }
+}
+
+/// Whether something ending at `to` is far enough ahead that its forecast
+/// is less certain
+pub fn is_uncertain(to: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    to > now + Duration::hours(CERTAIN_HOURS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn forward_windows() {
+        assert_eq!(Horizon::Hours24.forward(), "fw24h");
+        assert_eq!(Horizon::Hours48.forward(), "fw48h");
+        assert_eq!(Horizon::Hours48.duration(), Duration::days(2));
+    }
+
+    #[test]
+    fn less_certain_after_24_hours() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+
+        assert!(!is_uncertain(now + Duration::hours(24), now));
+        assert!(is_uncertain(
+            now + Duration::hours(24) + Duration::minutes(30),
+            now
+        ));
+    }
+}
@@ -1,8 +1,16 @@
+use std::fmt;
+
 use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 
+use super::fuel::Fuel;
+
 pub const INTENSITY_API: &str = "https://api.carbonintensity.org.uk";
 
+/// How far a generation mix's percentages can add up to something other
+/// than 100, as the API rounds each of them to one decimal place
+const MIX_TOLERANCE: f32 = 1.0;
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Period {
     #[serde(deserialize_with = "period_date_time_deserialize")]
@@ -18,21 +26,174 @@ pub struct Period {
 pub struct Intensity {
     pub forecast: i32,
     pub actual: Option<i32>,
-    pub index: String,
+    #[serde(with = "api_name")]
+    pub index: IntensityIndex,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GenerationMix {
-    pub fuel: String,
+    #[serde(with = "api_name")]
+    pub fuel: Fuel,
     #[serde(rename = "perc")]
     pub percentage: f32,
 }
 
+/// Whether we can trust a period's generation mix
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MixQuality {
+    /// The percentages add up to 100, and no fuel is listed twice
+    Valid,
+    #[default]
+    Missing,
+    Inconsistent,
+}
+
+impl MixQuality {
+    pub fn new(mix: Option<&[GenerationMix]>) -> Self {
+        let Some(mix) = mix.filter(|mix| !mix.is_empty()) else {
+            return MixQuality::Missing;
+        };
+
+        let total: f32 = mix.iter().map(|mix| mix.percentage).sum();
+        let negative = mix.iter().any(|mix| mix.percentage < 0.0);
+        let repeated = mix
+            .iter()
+            .enumerate()
+            .any(|(i, a)| mix[..i].iter().any(|b| a.fuel == b.fuel));
+        if negative || repeated || (total - 100.0).abs() > MIX_TOLERANCE {
+            MixQuality::Inconsistent
+        } else {
+            MixQuality::Valid
+        }
+    }
+}
+
+impl Period {
+    pub fn mix_quality(&self) -> MixQuality {
+        MixQuality::new(self.generation_mix.as_deref())
+    }
+}
+
+/// Consecutive half-hour periods from `start`, with the given forecasts, for
+/// the tests
+#[cfg(test)]
+pub(crate) fn periods(start: DateTime<Utc>, forecasts: &[i32]) -> Vec<Period> {
+    forecasts
+        .iter()
+        .enumerate()
+        .map(|(i, &forecast)| Period {
+            from: start + chrono::Duration::minutes(30 * i as i64),
+            to: start + chrono::Duration::minutes(30 * (i as i64 + 1)),
+            intensity: Some(Intensity {
+                forecast,
+                actual: None,
+                index: IntensityIndex::Moderate,
+            }),
+            generation_mix: None,
+        })
+        .collect()
+}
+
+/// National forecasts and their generation mix come from separate requests,
+/// so this copies each period's mix from the matching period in `mixes`
+pub fn attach_mix(periods: &mut [Period], mixes: &[Period]) {
+    for period in periods {
+        if let Some(mix) = mixes.iter().find(|mix| mix.from == period.from) {
+            period.generation_mix = mix.generation_mix.clone();
+        }
+    }
+}
+
+/// The API's rating of an intensity, in order from lowest to highest
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntensityIndex {
+    VeryLow,
+    Low,
+    #[default]
+    Moderate,
+    High,
+    VeryHigh,
+    /// A rating we don't know about, named as the API names it
+    Unknown(String),
+}
+
+impl ApiName for IntensityIndex {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "very low" => Self::VeryLow,
+            "low" => Self::Low,
+            "moderate" => Self::Moderate,
+            "high" => Self::High,
+            "very high" => Self::VeryHigh,
+            _ => Self::Unknown(name.to_string()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::VeryLow => "very low",
+            Self::Low => "low",
+            Self::Moderate => "moderate",
+            Self::High => "high",
+            Self::VeryHigh => "very high",
+            Self::Unknown(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for IntensityIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::VeryLow => "Very low",
+            Self::Low => "Low",
+            Self::Moderate => "Moderate",
+            Self::High => "High",
+            Self::VeryHigh => "Very high",
+            Self::Unknown(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+/// Something the API refers to by name, like a fuel, which we keep as an
+/// enum so that the shells get one too
+pub trait ApiName {
+    fn from_name(name: &str) -> Self;
+    fn name(&self) -> &str;
+}
+
+/// (De)serializes an `ApiName` as its name, for the API's responses and so
+/// that the stored model doesn't depend on the enum's layout
+pub mod api_name {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::ApiName;
+
+    pub fn serialize<T: ApiName, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.name())
+    }
+
+    pub fn deserialize<'de, T: ApiName, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(T::from_name(&name))
+    }
+}
+
+/// The period that contains the given time, if there is one
+pub fn current(periods: &[Period], time: DateTime<Utc>) -> Option<&Period> {
+    periods.iter().find(|p| p.from <= time && time < p.to)
+}
+
 pub fn period_date_time_deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    const FORMAT: &'static str = "%Y-%m-%dT%H:%M%Z";
+    const FORMAT: &str = "%Y-%m-%dT%H:%M%Z";
     let s = String::deserialize(deserializer)?;
     Utc.datetime_from_str(&s, FORMAT)
         .map_err(serde::de::Error::custom)
@@ -58,6 +219,7 @@ mod tests {
         "#;
 
         let data: Period = serde_json::from_str(json_str).unwrap();
+        assert_eq!(data.intensity.unwrap().index, IntensityIndex::Moderate);
         assert_eq!(
             data.from,
             Utc.with_ymd_and_hms(2023, 7, 4, 23, 0, 0).unwrap()
@@ -67,4 +229,66 @@ mod tests {
             Utc.with_ymd_and_hms(2023, 7, 4, 23, 30, 0).unwrap()
         );
     }
+
+    #[test]
+    fn unknown_names() {
+        let json = r#"{ "fuel": "fusion", "perc": 1.5 }"#;
+
+        let mix: GenerationMix = serde_json::from_str(json).unwrap();
+
+        assert_eq!(mix.fuel, Fuel::Unknown("fusion".to_string()));
+        assert_eq!(mix.fuel.to_string(), "fusion");
+        assert_eq!(serde_json::to_string(&mix).unwrap(), json.replace(' ', ""));
+        assert_eq!(
+            IntensityIndex::from_name("extreme"),
+            IntensityIndex::Unknown("extreme".to_string())
+        );
+        assert!(IntensityIndex::VeryHigh > IntensityIndex::High);
+        assert_eq!(IntensityIndex::VeryHigh.to_string(), "Very high");
+    }
+
+    fn mix(fuels: &[(Fuel, f32)]) -> Vec<GenerationMix> {
+        fuels
+            .iter()
+            .map(|(fuel, percentage)| GenerationMix {
+                fuel: fuel.clone(),
+                percentage: *percentage,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mix_quality() {
+        let valid = mix(&[(Fuel::Gas, 40.1), (Fuel::Wind, 59.8)]);
+        let short = mix(&[(Fuel::Gas, 40.1), (Fuel::Wind, 21.3)]);
+        let repeated = mix(&[(Fuel::Gas, 50.0), (Fuel::Gas, 50.0)]);
+        let negative = mix(&[(Fuel::Gas, 101.0), (Fuel::Wind, -1.0)]);
+
+        assert_eq!(MixQuality::new(Some(&valid)), MixQuality::Valid);
+        assert_eq!(MixQuality::new(Some(&short)), MixQuality::Inconsistent);
+        assert_eq!(MixQuality::new(Some(&repeated)), MixQuality::Inconsistent);
+        assert_eq!(MixQuality::new(Some(&negative)), MixQuality::Inconsistent);
+        assert_eq!(MixQuality::new(Some(&[])), MixQuality::Missing);
+        assert_eq!(MixQuality::new(None), MixQuality::Missing);
+    }
+
+    #[test]
+    fn attach_mix_to_matching_periods() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 4, 23, 0, 0).unwrap();
+        let period = |from| Period {
+            from,
+            to: from + chrono::Duration::minutes(30),
+            ..Default::default()
+        };
+        let mut periods = vec![period(start), period(start + chrono::Duration::minutes(30))];
+        let mixes = vec![Period {
+            generation_mix: Some(mix(&[(Fuel::Wind, 100.0)])),
+            ..period(start)
+        }];
+
+        attach_mix(&mut periods, &mixes);
+
+        assert_eq!(periods[0].mix_quality(), MixQuality::Valid);
+        assert_eq!(periods[1].mix_quality(), MixQuality::Missing);
+    }
 }
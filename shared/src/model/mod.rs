@@ -1,22 +1,56 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use self::{intensity::Period, location::Location};
+use self::{
+    error::Error,
+    factors::Factors,
+    footprint::Usage,
+    freshness::Freshness,
+    history::HistoryRange,
+    horizon::Horizon,
+    intensity::Period,
+    location::Location,
+    postcode::Search,
+    refresh::{Background, Failures, RefreshPolicy},
+    regional::Region,
+    scheduling::Job,
+    sites::SiteForecast,
+    thresholds::{Notified, Threshold},
+    zone::Zone,
+};
 
+pub mod accuracy;
+pub mod comparison;
+pub mod error;
 pub mod factors;
+pub mod footprint;
+pub mod freshness;
+pub mod fuel;
 pub mod global;
+pub mod history;
+pub mod horizon;
 pub mod intensity;
 pub mod location;
 pub mod national_intensity;
 pub mod national_mix;
 pub mod postcode;
+pub mod refresh;
 pub mod regional;
+pub mod scheduling;
+pub mod sites;
+pub mod storage;
+pub mod summary;
+pub mod thresholds;
+pub mod zone;
 
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq)]
 pub enum Mode {
     #[default]
     National,
     Local,
+    Regions,
+    /// Local and national side by side
+    Compare,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
@@ -32,6 +66,12 @@ pub trait DataSet {}
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
 pub struct National {
     pub generation_mix: Vec<Period>,
+    /// The intensity and the mix are fetched separately, so we keep each
+    /// one's error until both have arrived
+    #[serde(skip)]
+    pub intensity_error: Option<Error>,
+    #[serde(skip)]
+    pub mix_error: Option<Error>,
 }
 
 impl DataSet for National {}
@@ -39,20 +79,292 @@ impl DataSet for National {}
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
 pub struct Local {
     pub location: Option<Location>,
+    /// A postcode or outcode entered by the user, used instead of the
+    /// device's location
+    pub search: Option<Search>,
 }
 impl DataSet for Local {}
 
-#[derive(Default, Serialize)]
+/// Every DNO region's current intensity, and the forecast (in `Data::periods`)
+/// for the one the user has chosen to look at
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Regions {
+    pub regions: Vec<Region>,
+    pub last_updated: DateTime<Utc>,
+    pub selected: Option<i64>,
+    /// The list and the forecast are fetched separately, so we keep each
+    /// one's error until it's fetched successfully
+    #[serde(skip)]
+    pub regions_error: Option<Error>,
+    #[serde(skip)]
+    pub region_error: Option<Error>,
+}
+impl DataSet for Regions {}
+
+/// Two places the user has chosen to compare, and the load (kW) to compare
+/// the emissions of. Each place has its own forecast, so `Data::periods`
+/// isn't used.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Sites {
+    pub forecasts: Vec<SiteForecast>,
+    pub load_kw: f64,
+    /// Each site's forecast is fetched separately, so we keep the first
+    /// error until they've all arrived
+    #[serde(skip)]
+    pub site_error: Option<Error>,
+}
+impl DataSet for Sites {}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct History {
+    pub range: Option<HistoryRange>,
+    /// Long ranges are fetched in chunks, so we keep the first chunk's error
+    /// until they've all arrived
+    #[serde(skip)]
+    pub chunk_error: Option<Error>,
+}
+impl DataSet for History {}
+
+/// Which set of local data we're looking at: the device's location (or a
+/// postcode entered in its place), or one of the user's saved locations
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub enum Place {
+    #[default]
+    Current,
+    Saved(String),
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct SavedLocation {
+    pub name: String,
+    pub data: Data<Local>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
 pub struct Data<T: DataSet + Serialize> {
     pub scope: T,
     pub periods: Vec<Period>,
+    /// The 24 hours before the forecast, to compare it with
+    #[serde(default)]
+    pub past: Vec<Period>,
     pub last_updated: DateTime<Utc>,
+    /// Only for this session, so a previous session's error isn't shown
+    #[serde(skip)]
+    pub error: Option<Error>,
+    #[serde(default)]
+    pub failures: Failures,
+    /// What we're still waiting for while fetching this data
+    #[serde(skip)]
+    pub pending: Vec<Operation>,
+    /// Changed whenever the data is cleared (or replaced), to one from
+    /// `Model::next_generation`. Requests carry the generation they were
+    /// made in, so that we can ignore late responses.
+    #[serde(skip)]
+    pub generation: u64,
+}
+
+/// Something we've asked the shell for (including HTTP requests) while
+/// fetching a set of data
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Time,
+    Location,
+    Postcode,
+    Lookup,
+    Regional,
+    NationalIntensity,
+    NationalMix,
+    Regions,
+    Region,
+    History,
+    Site,
+    Past,
+    Factors,
+}
+
+impl<T: DataSet + Serialize> Data<T> {
+    /// The data goes stale when it's due to be fetched again, and expires
+    /// after the horizon, when the forecast is all in the past
+    pub fn freshness(
+        &self,
+        now: DateTime<Utc>,
+        policy: &RefreshPolicy,
+        horizon: Horizon,
+    ) -> Freshness {
+        Freshness::new(self.last_updated, now, policy.min_age(), horizon.duration())
+    }
+
+    /// Forgets any fetched data, so that it's fetched again on the next
+    /// request, and moves on to a new generation
+    pub fn clear(&mut self, generation: u64) {
+        self.periods.clear();
+        self.past.clear();
+        self.last_updated = DateTime::default();
+        self.error = None;
+        self.failures.reset();
+        self.pending.clear();
+        self.generation = generation;
+    }
+
+    /// Takes over what `current` is waiting for, when this data replaces it,
+    /// so that responses to its requests are still handled and stale ones
+    /// are still ignored
+    pub fn take_requests(&mut self, current: &mut Data<T>) {
+        self.pending = std::mem::take(&mut current.pending);
+        self.generation = current.generation;
+    }
+
+    pub fn start(&mut self, operation: Operation) {
+        self.pending.push(operation);
+    }
+
+    /// Marks one of the pending operations of this kind as finished
+    pub fn finish(&mut self, operation: Operation) {
+        if let Some(index) = self.pending.iter().position(|op| *op == operation) {
+            self.pending.swap_remove(index);
+        }
+    }
+
+    pub fn is_pending(&self, operation: Operation) -> bool {
+        self.pending.contains(&operation)
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Whether we're waiting for the data itself, rather than just the time
+    pub fn is_fetching(&self) -> bool {
+        self.pending.iter().any(|op| *op != Operation::Time)
+    }
+}
+
+impl Data<Local> {
+    /// Whether we know where to fetch local data for, from the device's
+    /// location or a postcode the user has entered
+    pub fn has_place(&self) -> bool {
+        self.scope.location.is_some() || self.scope.search.is_some()
+    }
 }
 
-#[derive(Default, Serialize)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
 pub struct Model {
     pub time: DateTime<Utc>,
     pub mode: Mode,
     pub national: Data<National>,
     pub local: Data<Local>,
+    pub regions: Data<Regions>,
+    pub history: Data<History>,
+    #[serde(default)]
+    pub sites: Data<Sites>,
+    pub saved: Vec<SavedLocation>,
+    pub place: Place,
+    pub job: Option<Job>,
+    /// Why the last job entered couldn't be scheduled
+    #[serde(skip)]
+    pub job_error: Option<Error>,
+    pub usage: Option<Usage>,
+    /// Why the last usage entered couldn't be used
+    #[serde(skip)]
+    pub usage_error: Option<Error>,
+    /// Emission factors from the API, once fetched
+    pub factors: Option<Factors>,
+    #[serde(default)]
+    pub refresh: RefreshPolicy,
+    #[serde(default)]
+    pub horizon: Horizon,
+    #[serde(default)]
+    pub zone: Zone,
+    #[serde(default)]
+    pub thresholds: Vec<Threshold>,
+    /// Why the last threshold entered couldn't be added
+    #[serde(skip)]
+    pub threshold_error: Option<Error>,
+    /// Periods we've scheduled a notification for
+    #[serde(default)]
+    pub notified: Vec<Notified>,
+    #[serde(skip)]
+    pub background: Option<Background>,
+    /// Requests that aren't for any one set of data, like the emission factors
+    #[serde(skip)]
+    pub pending: Vec<Operation>,
+    /// The last generation given to any data, so that data that's removed
+    /// and added again never reuses one
+    #[serde(skip)]
+    pub generation: u64,
+}
+
+impl Model {
+    pub fn local(&self, place: &Place) -> Option<&Data<Local>> {
+        match place {
+            Place::Current => Some(&self.local),
+            Place::Saved(name) => self
+                .saved
+                .iter()
+                .find(|saved| &saved.name == name)
+                .map(|saved| &saved.data),
+        }
+    }
+
+    /// The local data for a place, if it hasn't been removed
+    pub fn local_mut(&mut self, place: &Place) -> Option<&mut Data<Local>> {
+        match place {
+            Place::Current => Some(&mut self.local),
+            Place::Saved(name) => self
+                .saved
+                .iter_mut()
+                .find(|saved| &saved.name == name)
+                .map(|saved| &mut saved.data),
+        }
+    }
+
+    /// The local data for the selected place
+    pub fn selected(&self) -> &Data<Local> {
+        self.local(&self.place).unwrap_or(&self.local)
+    }
+
+    pub fn selected_mut(&mut self) -> &mut Data<Local> {
+        let index = match &self.place {
+            Place::Current => None,
+            Place::Saved(name) => self.saved.iter().position(|saved| &saved.name == name),
+        };
+        match index {
+            Some(index) => &mut self.saved[index].data,
+            None => &mut self.local,
+        }
+    }
+
+    /// Replaces the model with one restored from storage, keeping the state
+    /// that isn't stored, as the app may already have made requests
+    pub fn restore(&mut self, mut stored: Model) {
+        stored.national.take_requests(&mut self.national);
+        stored.national.scope.intensity_error = self.national.scope.intensity_error.take();
+        stored.national.scope.mix_error = self.national.scope.mix_error.take();
+        stored.local.take_requests(&mut self.local);
+        stored.regions.take_requests(&mut self.regions);
+        stored.history.take_requests(&mut self.history);
+        stored.sites.take_requests(&mut self.sites);
+        for saved in &mut stored.saved {
+            if let Some(current) = self.saved.iter_mut().find(|c| c.name == saved.name) {
+                saved.data.take_requests(&mut current.data);
+            }
+        }
+        stored.time = stored.time.max(self.time);
+        stored.background = self.background.take();
+        stored.pending = std::mem::take(&mut self.pending);
+        stored.generation = self.generation;
+        *self = stored;
+    }
+
+    /// A generation that no data has had before
+    pub fn next_generation(&mut self) -> u64 {
+        self.generation += 1;
+        self.generation
+    }
+
+    /// The emission factors from the API, or the built-in table if they
+    /// haven't been fetched
+    pub fn factors(&self) -> Factors {
+        self.factors.clone().unwrap_or_else(Factors::new)
+    }
 }
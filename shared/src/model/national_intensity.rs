@@ -1,17 +1,128 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::intensity::{Period, INTENSITY_API};
+use super::{
+    horizon::Horizon,
+    intensity::{Period, INTENSITY_API},
+};
 
-pub(crate) fn url(from: &DateTime<Utc>) -> Url {
+/// The longest range the API will return from `/intensity/{from}/{to}`
+pub const MAX_RANGE_DAYS: i64 = 14;
+
+pub(crate) fn url(from: &DateTime<Utc>, horizon: Horizon) -> Url {
     let from = from.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let forward = horizon.forward();
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!("/intensity/{from}/{forward}")).unwrap()
+}
+
+pub(crate) fn past_url(to: &DateTime<Utc>) -> Url {
+    let to = to.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!("/intensity/{to}/pt24h")).unwrap()
+}
+
+pub(crate) fn range_url(from: &DateTime<Utc>, to: &DateTime<Utc>) -> Url {
+    let from = from.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let to = to.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!("/intensity/{from}/{to}")).unwrap()
+}
+
+/// Splits a range into as many requests as needed to stay within the
+/// API's maximum range
+pub(crate) fn range_urls(from: &DateTime<Utc>, to: &DateTime<Utc>) -> Vec<Url> {
+    let mut urls = Vec::new();
+    let mut start = *from;
+    while start < *to {
+        let end = (start + Duration::days(MAX_RANGE_DAYS)).min(*to);
+        urls.push(range_url(&start, &end));
+        start = end;
+    }
+    urls
+}
+
+pub(crate) fn date_url(date: &NaiveDate) -> Url {
+    let date = date.format("%Y-%m-%d");
     let base = Url::parse(INTENSITY_API).unwrap();
-    let url = base.join(&format!("/intensity/{from}/fw24h")).unwrap();
-    url
+    base.join(&format!("/intensity/date/{date}")).unwrap()
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NationalResponse {
     pub data: Vec<Period>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn range_within_maximum_is_one_request() {
+        let from = Utc.with_ymd_and_hms(2023, 7, 1, 12, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2023, 7, 8, 12, 0, 0).unwrap();
+
+        let urls: Vec<String> = range_urls(&from, &to)
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://api.carbonintensity.org.uk/intensity/2023-07-01T12:00Z/2023-07-08T12:00Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn long_range_is_split_into_chunks() {
+        let from = Utc.with_ymd_and_hms(2023, 6, 6, 12, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2023, 7, 6, 12, 0, 0).unwrap();
+
+        let urls: Vec<String> = range_urls(&from, &to)
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://api.carbonintensity.org.uk/intensity/2023-06-06T12:00Z/2023-06-20T12:00Z",
+                "https://api.carbonintensity.org.uk/intensity/2023-06-20T12:00Z/2023-07-04T12:00Z",
+                "https://api.carbonintensity.org.uk/intensity/2023-07-04T12:00Z/2023-07-06T12:00Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn forecast_urls() {
+        let from = Utc.with_ymd_and_hms(2023, 7, 6, 20, 30, 0).unwrap();
+
+        assert_eq!(
+            url(&from, Horizon::Hours24).as_str(),
+            "https://api.carbonintensity.org.uk/intensity/2023-07-06T20:30Z/fw24h"
+        );
+        assert_eq!(
+            url(&from, Horizon::Hours48).as_str(),
+            "https://api.carbonintensity.org.uk/intensity/2023-07-06T20:30Z/fw48h"
+        );
+    }
+
+    #[test]
+    fn past_and_date_urls() {
+        let to = Utc.with_ymd_and_hms(2023, 7, 6, 20, 30, 0).unwrap();
+        let date = NaiveDate::from_ymd_opt(2023, 7, 4).unwrap();
+
+        assert_eq!(
+            past_url(&to).as_str(),
+            "https://api.carbonintensity.org.uk/intensity/2023-07-06T20:30Z/pt24h"
+        );
+        assert_eq!(
+            date_url(&date).as_str(),
+            "https://api.carbonintensity.org.uk/intensity/date/2023-07-04"
+        );
+    }
+}
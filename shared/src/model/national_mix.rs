@@ -1,18 +1,20 @@
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde::Serialize;
 use url::Url;
 
-use super::intensity::{Period, INTENSITY_API};
+use super::{
+    horizon::Horizon,
+    intensity::{Period, INTENSITY_API},
+};
 
-pub(crate) fn url(from: &DateTime<Utc>) -> Url {
-    let to = *from + Duration::hours(24);
+pub(crate) fn url(from: &DateTime<Utc>, horizon: Horizon) -> Url {
+    let to = *from + horizon.duration();
 
     let from = from.format("%Y-%m-%dT%H:%M").to_string() + "Z";
     let to = to.format("%Y-%m-%dT%H:%M").to_string() + "Z";
     let base = Url::parse(INTENSITY_API).unwrap();
-    let url = base.join(&format!("/generation/{from}/{to}")).unwrap();
-    url
+    base.join(&format!("/generation/{from}/{to}")).unwrap()
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1,9 +1,11 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use url::Url;
 
 use crate::capabilities::location::Coordinate;
 
+use super::error::Error;
+
 pub const BASE_URL: &str = "https://api.postcodes.io";
 
 pub fn url() -> String {
@@ -12,6 +14,79 @@ pub fn url() -> String {
     url.to_string()
 }
 
+/// A postcode or outcode typed in by the user, normalised to upper case with
+/// a single space between the outward and inward codes (e.g. "KT1 2UD").
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Search {
+    Postcode(String),
+    Outcode(String),
+}
+
+impl Search {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let code: String = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        let invalid = || Error::InvalidPostcode(text.trim().to_string());
+
+        if !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+
+        // the inward code is always a digit followed by two letters
+        let (outward, inward) = if code.len() > 4 {
+            code.split_at(code.len() - 3)
+        } else {
+            (code.as_str(), "")
+        };
+        let mut chars = inward.chars();
+        let valid_inward = inward.is_empty()
+            || (matches!(chars.next(), Some(c) if c.is_ascii_digit())
+                && chars.all(|c| c.is_ascii_alphabetic()));
+        let valid_outward = (2..=4).contains(&outward.len())
+            && outward.starts_with(|c: char| c.is_ascii_alphabetic())
+            && outward.contains(|c: char| c.is_ascii_digit());
+        if !valid_outward || !valid_inward {
+            return Err(invalid());
+        }
+
+        Ok(if inward.is_empty() {
+            Search::Outcode(outward.to_string())
+        } else {
+            Search::Postcode(format!("{outward} {inward}"))
+        })
+    }
+
+    pub fn url(&self) -> String {
+        let base = Url::parse(BASE_URL).unwrap();
+        let url = match self {
+            Search::Postcode(postcode) => base
+                .join(&format!("/postcodes/{}", postcode.replace(' ', "")))
+                .unwrap(),
+            Search::Outcode(outcode) => base.join(&format!("/outcodes/{outcode}")).unwrap(),
+        };
+        url.to_string()
+    }
+
+    /// The outward code, e.g. "KT1" for "KT1 2UD"
+    pub fn outcode(&self) -> &str {
+        match self {
+            Search::Postcode(postcode) => postcode.split(' ').next().unwrap_or(postcode),
+            Search::Outcode(outcode) => outcode,
+        }
+    }
+}
+
+impl std::fmt::Display for Search {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Search::Postcode(code) | Search::Outcode(code) => write!(f, "{code}"),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct Query {
     pub lat: f64,
@@ -30,7 +105,15 @@ impl From<Coordinate> for Query {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PostcodeResponse {
     pub status: i64,
-    pub result: Vec<Postcode>,
+    pub result: Option<Vec<Postcode>>,
+}
+
+impl PostcodeResponse {
+    /// The postcode nearest to the queried location, if there is one
+    /// (there is no result for locations far from any postcode).
+    pub fn nearest(self) -> Option<Postcode> {
+        self.result?.into_iter().next()
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -80,3 +163,110 @@ pub struct Codes {
     pub lau2: String,
     pub pfa: String,
 }
+
+/// The response to looking up a postcode (`/postcodes/{postcode}`) or an
+/// outcode (`/outcodes/{outcode}`), which share the fields we need.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupResponse {
+    pub status: i64,
+    pub result: Lookup,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lookup {
+    pub outcode: String,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    /// A postcode is in one district, but an outcode can span several
+    #[serde(deserialize_with = "one_or_many")]
+    pub admin_district: Vec<String>,
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Option<String>),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => one.into_iter().collect(),
+        OneOrMany::Many(many) => many,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_postcode() {
+        for text in ["KT1 2UD", "kt12ud", " Kt1  2ud "] {
+            assert_eq!(
+                Search::parse(text),
+                Ok(Search::Postcode("KT1 2UD".to_string()))
+            );
+        }
+        assert_eq!(
+            Search::parse("sw1a1aa"),
+            Ok(Search::Postcode("SW1A 1AA".to_string()))
+        );
+        assert_eq!(
+            Search::parse("G1 1XQ"),
+            Ok(Search::Postcode("G1 1XQ".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_outcode() {
+        for (text, outcode) in [("kt1", "KT1"), ("G1", "G1"), ("sw1a ", "SW1A")] {
+            assert_eq!(
+                Search::parse(text),
+                Ok(Search::Outcode(outcode.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in ["", "K", "123", "KT1 UD2", "KT1-2UD", "KTTT1 2UD"] {
+            assert_eq!(
+                Search::parse(text),
+                Err(Error::InvalidPostcode(text.trim().to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_urls() {
+        assert_eq!(
+            Search::Postcode("KT1 2UD".to_string()).url(),
+            "https://api.postcodes.io/postcodes/KT12UD"
+        );
+        assert_eq!(
+            Search::Outcode("KT1".to_string()).url(),
+            "https://api.postcodes.io/outcodes/KT1"
+        );
+    }
+
+    #[test]
+    fn outcode_of_search() {
+        assert_eq!(Search::Postcode("KT1 2UD".to_string()).outcode(), "KT1");
+        assert_eq!(Search::Outcode("G1".to_string()).outcode(), "G1");
+    }
+
+    #[test]
+    fn lookup_district() {
+        let outcode: LookupResponse =
+            serde_json::from_str(include_str!("../fixtures/outcode.json")).unwrap();
+        let postcode: LookupResponse =
+            serde_json::from_str(include_str!("../fixtures/postcode_lookup.json")).unwrap();
+
+        assert_eq!(outcode.result.admin_district, vec!["Glasgow City"]);
+        assert_eq!(postcode.result.admin_district, vec!["Glasgow City"]);
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::intensity::Period;
+
+/// The longest any of the policy's times can be, which is a week
+pub const MAX_MINUTES: i64 = 7 * 24 * 60;
+
+/// When to fetch data again, set by the shell with `Event::SetRefreshPolicy`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    /// Data is only fetched again once it's older than this
+    pub min_age_minutes: i64,
+    /// Only fetch again once a new half hour has started, because that's
+    /// when the API publishes new data
+    pub align_to_half_hour: bool,
+    /// How long to wait before trying again after a failure. This doubles
+    /// for each consecutive failure, up to `max_backoff_minutes`.
+    pub backoff_minutes: i64,
+    pub max_backoff_minutes: i64,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self {
+            min_age_minutes: 30,
+            align_to_half_hour: false,
+            backoff_minutes: 1,
+            max_backoff_minutes: 60,
+        }
+    }
+}
+
+/// Consecutive failures to fetch a set of data
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Failures {
+    pub count: u32,
+    pub last: DateTime<Utc>,
+}
+
+impl Failures {
+    /// Records a failed fetch. Requests made at the same time are part of the
+    /// same attempt, so count as a single failure.
+    pub fn record(&mut self, time: DateTime<Utc>) {
+        if self.count == 0 || self.last != time {
+            self.count += 1;
+            self.last = time;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Failures::default();
+    }
+}
+
+impl RefreshPolicy {
+    /// The policy with each of its times between zero and `MAX_MINUTES`, so
+    /// that anything the shell sends makes sense
+    pub fn clamped(self) -> Self {
+        let clamp = |minutes: i64| minutes.clamp(0, MAX_MINUTES);
+        Self {
+            min_age_minutes: clamp(self.min_age_minutes),
+            backoff_minutes: clamp(self.backoff_minutes),
+            max_backoff_minutes: clamp(self.max_backoff_minutes),
+            ..self
+        }
+    }
+
+    /// Whether to fetch data that was last updated at `last_updated`. If
+    /// `force` is set, we fetch regardless of the policy.
+    pub fn should_refresh(
+        &self,
+        last_updated: DateTime<Utc>,
+        failures: &Failures,
+        now: DateTime<Utc>,
+        force: bool,
+    ) -> bool {
+        if force {
+            return true;
+        }
+        if failures.count > 0 && now - failures.last < self.backoff(failures.count) {
+            return false;
+        }
+
+        let old_enough = now - last_updated > self.min_age();
+        if self.align_to_half_hour {
+            old_enough && last_updated < half_hour_start(now)
+        } else {
+            old_enough
+        }
+    }
+
+    /// When data that was last updated at `last_updated` is next due to be
+    /// fetched, i.e. `should_refresh` is true from just after this time
+    pub fn next_refresh(&self, last_updated: DateTime<Utc>, failures: &Failures) -> DateTime<Utc> {
+        let mut next = last_updated + self.min_age();
+        if self.align_to_half_hour {
+            next = next.max(half_hour_start(last_updated) + Duration::minutes(30));
+        }
+        if failures.count > 0 {
+            next = next.max(failures.last + self.backoff(failures.count));
+        }
+        next
+    }
+
+    /// How long data is fresh for, after which we fetch it again
+    pub fn min_age(&self) -> Duration {
+        Duration::minutes(self.min_age_minutes)
+    }
+
+    /// How long to wait after `count` consecutive failures
+    pub fn backoff(&self, count: u32) -> Duration {
+        let minutes = self
+            .backoff_minutes
+            .saturating_mul(2_i64.saturating_pow(count.saturating_sub(1)))
+            .min(self.max_backoff_minutes);
+        Duration::minutes(minutes)
+    }
+}
+
+/// A refresh started by the shell's background task, during which we don't
+/// render. We keep the periods we had at the start, to tell whether the
+/// refresh changed anything.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Background {
+    pub national: Vec<Period>,
+    pub local: Vec<Period>,
+    /// The shell has sent another event (e.g. because the app has come to
+    /// the foreground), so we render as usual again
+    pub foreground: bool,
+}
+
+/// The start of the half hour containing `time`
+fn half_hour_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::minutes(30)).unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn time(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 7, 6, hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn refresh_after_min_age() {
+        let policy = RefreshPolicy {
+            min_age_minutes: 10,
+            ..Default::default()
+        };
+        let failures = Failures::default();
+
+        assert!(!policy.should_refresh(time(20, 30), &failures, time(20, 40), false));
+        assert!(policy.should_refresh(time(20, 30), &failures, time(20, 41), false));
+        assert!(policy.should_refresh(DateTime::default(), &failures, time(20, 41), false));
+    }
+
+    #[test]
+    fn refresh_aligned_to_half_hour() {
+        let policy = RefreshPolicy {
+            min_age_minutes: 5,
+            align_to_half_hour: true,
+            ..Default::default()
+        };
+        let failures = Failures::default();
+
+        // old enough, but no new data yet
+        assert!(!policy.should_refresh(time(20, 31), &failures, time(20, 59), false));
+        // a new half hour has started
+        assert!(policy.should_refresh(time(20, 31), &failures, time(21, 0), false));
+        // a new half hour has started, but the data is too new
+        assert!(!policy.should_refresh(time(20, 58), &failures, time(21, 1), false));
+    }
+
+    #[test]
+    fn force_refresh() {
+        let policy = RefreshPolicy::default();
+        let failures = Failures {
+            count: 3,
+            last: time(20, 40),
+        };
+
+        assert!(policy.should_refresh(time(20, 40), &Failures::default(), time(20, 41), true));
+        assert!(policy.should_refresh(time(20, 0), &failures, time(20, 41), true));
+    }
+
+    #[test]
+    fn back_off_exponentially_after_failures() {
+        let policy = RefreshPolicy {
+            backoff_minutes: 2,
+            max_backoff_minutes: 10,
+            ..Default::default()
+        };
+        let mut failures = Failures::default();
+
+        failures.record(time(20, 0));
+        assert!(!policy.should_refresh(DateTime::default(), &failures, time(20, 1), false));
+        assert!(policy.should_refresh(DateTime::default(), &failures, time(20, 2), false));
+
+        failures.record(time(20, 2));
+        assert_eq!(failures.count, 2);
+        assert!(!policy.should_refresh(DateTime::default(), &failures, time(20, 5), false));
+        assert!(policy.should_refresh(DateTime::default(), &failures, time(20, 6), false));
+
+        assert_eq!(policy.backoff(3), Duration::minutes(8));
+        assert_eq!(policy.backoff(4), Duration::minutes(10));
+        assert_eq!(policy.backoff(100), Duration::minutes(10));
+
+        failures.reset();
+        assert!(policy.should_refresh(DateTime::default(), &failures, time(20, 6), false));
+    }
+
+    #[test]
+    fn clamp_times() {
+        let policy = RefreshPolicy {
+            min_age_minutes: i64::MAX,
+            align_to_half_hour: true,
+            backoff_minutes: -5,
+            max_backoff_minutes: 60,
+        };
+
+        assert_eq!(
+            policy.clamped(),
+            RefreshPolicy {
+                min_age_minutes: MAX_MINUTES,
+                align_to_half_hour: true,
+                backoff_minutes: 0,
+                max_backoff_minutes: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn failures_at_the_same_time_count_once() {
+        let mut failures = Failures::default();
+
+        failures.record(time(20, 0));
+        failures.record(time(20, 0));
+
+        assert_eq!(failures.count, 1);
+    }
+
+    #[test]
+    fn next_refresh_after_min_age_half_hour_and_backoff() {
+        let policy = RefreshPolicy {
+            min_age_minutes: 10,
+            ..Default::default()
+        };
+        let mut failures = Failures::default();
+
+        assert_eq!(policy.next_refresh(time(20, 5), &failures), time(20, 15));
+
+        let aligned = RefreshPolicy {
+            align_to_half_hour: true,
+            ..policy.clone()
+        };
+        assert_eq!(aligned.next_refresh(time(20, 5), &failures), time(20, 30));
+
+        failures.record(time(20, 40));
+        failures.record(time(20, 41));
+        assert_eq!(policy.next_refresh(time(20, 5), &failures), time(20, 43));
+    }
+}
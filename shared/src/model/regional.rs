@@ -3,17 +3,58 @@ use serde::Deserialize;
 use serde::Serialize;
 use url::Url;
 
-use super::intensity::{Period, INTENSITY_API};
+use super::{
+    horizon::Horizon,
+    intensity::{period_date_time_deserialize, GenerationMix, Intensity, Period, INTENSITY_API},
+};
 
-pub(crate) fn url(from: &DateTime<Utc>, outcode: &str) -> Url {
+/// The 14 DNO (distribution network operator) regions have IDs 1 to 14. The
+/// API also reports England, Scotland and Wales as a whole, with IDs 15 to 17.
+pub(crate) const DNO_REGIONS: std::ops::RangeInclusive<i64> = 1..=14;
+
+pub(crate) fn url(from: &DateTime<Utc>, outcode: &str, horizon: Horizon) -> Url {
     let from = from.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let forward = horizon.forward();
     let base = Url::parse(INTENSITY_API).unwrap();
-    let url = base
-        .join(&format!(
-            "/regional/intensity/{from}/fw24h/postcode/{outcode}"
-        ))
-        .unwrap();
-    url
+    base.join(&format!(
+        "/regional/intensity/{from}/{forward}/postcode/{outcode}"
+    ))
+    .unwrap()
+}
+
+/// The 24 hours up to `to` for an outcode
+pub(crate) fn past_url(to: &DateTime<Utc>, outcode: &str) -> Url {
+    let to = to.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!(
+        "/regional/intensity/{to}/pt24h/postcode/{outcode}"
+    ))
+    .unwrap()
+}
+
+pub(crate) fn all_url() -> Url {
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join("/regional").unwrap()
+}
+
+/// The 24 hours up to `to` for a region
+pub(crate) fn region_past_url(to: &DateTime<Utc>, region_id: i64) -> Url {
+    let to = to.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!(
+        "/regional/intensity/{to}/pt24h/regionid/{region_id}"
+    ))
+    .unwrap()
+}
+
+pub(crate) fn region_url(from: &DateTime<Utc>, region_id: i64, horizon: Horizon) -> Url {
+    let from = from.format("%Y-%m-%dT%H:%M").to_string() + "Z";
+    let forward = horizon.forward();
+    let base = Url::parse(INTENSITY_API).unwrap();
+    base.join(&format!(
+        "/regional/intensity/{from}/{forward}/regionid/{region_id}"
+    ))
+    .unwrap()
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -28,6 +69,79 @@ pub struct Region {
     #[serde(rename = "dnoregion")]
     pub dno_region: String,
     pub shortname: String,
-    pub postcode: String,
+    pub postcode: Option<String>,
     pub data: Vec<Period>,
 }
+
+/// The current intensity of every region, from `/regional`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionsResponse {
+    pub data: Vec<RegionsPeriod>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionsPeriod {
+    #[serde(deserialize_with = "period_date_time_deserialize")]
+    pub from: DateTime<Utc>,
+    #[serde(deserialize_with = "period_date_time_deserialize")]
+    pub to: DateTime<Utc>,
+    pub regions: Vec<RegionIntensity>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionIntensity {
+    #[serde(rename = "regionid")]
+    pub region_id: i64,
+    #[serde(rename = "dnoregion")]
+    pub dno_region: String,
+    pub shortname: String,
+    pub intensity: Option<Intensity>,
+    #[serde(rename = "generationmix")]
+    pub generation_mix: Option<Vec<GenerationMix>>,
+}
+
+impl RegionsResponse {
+    /// Each DNO region, with a single period for the current intensity
+    pub fn regions(self) -> Vec<Region> {
+        self.data
+            .into_iter()
+            .flat_map(|period| {
+                let (from, to) = (period.from, period.to);
+                period.regions.into_iter().map(move |region| Region {
+                    region_id: region.region_id,
+                    dno_region: region.dno_region,
+                    shortname: region.shortname,
+                    postcode: None,
+                    data: vec![Period {
+                        from,
+                        to,
+                        intensity: region.intensity,
+                        generation_mix: region.generation_mix,
+                    }],
+                })
+            })
+            .filter(|region| DNO_REGIONS.contains(&region.region_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn past_urls() {
+        let to = DateTime::parse_from_rfc3339("2023-07-06T20:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            past_url(&to, "KT1").as_str(),
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/pt24h/postcode/KT1"
+        );
+        assert_eq!(
+            region_past_url(&to, 13).as_str(),
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/pt24h/regionid/13"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::Error,
+    horizon::{self, Horizon},
+    intensity::Period,
+};
+
+/// A flexible load (dishwasher, EV charge, batch job) that needs to run
+/// for `duration_minutes` and finish before `deadline`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Job {
+    pub duration_minutes: i64,
+    pub deadline: DateTime<Utc>,
+}
+
+impl Job {
+    /// A job with its deadline as an RFC 3339 time, e.g. "2023-07-05T07:00:00Z".
+    /// It has to fit in the forecast, and be able to finish by the deadline
+    /// if it starts now.
+    pub fn parse(
+        duration_minutes: i64,
+        deadline: &str,
+        now: DateTime<Utc>,
+        horizon: Horizon,
+    ) -> Result<Self, Error> {
+        if !(1..=horizon.hours() * 60).contains(&duration_minutes) {
+            return Err(Error::InvalidDuration(duration_minutes));
+        }
+        let parsed = DateTime::parse_from_rfc3339(deadline.trim())
+            .map_err(|_| Error::InvalidDate(deadline.to_string()))?
+            .with_timezone(&Utc);
+        if parsed < now + Duration::minutes(duration_minutes) {
+            return Err(Error::DeadlineTooSoon(deadline.to_string()));
+        }
+        Ok(Job {
+            duration_minutes,
+            deadline: parsed,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Window {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub average: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Schedule {
+    pub best: Window,
+    pub now: Window,
+    /// The best window is far enough ahead that its forecast is less
+    /// certain, which is only possible with a 48-hour horizon
+    pub uncertain: bool,
+}
+
+impl Schedule {
+    /// The reduction in average forecast intensity (gCO2/kWh) from waiting
+    /// for the best window instead of running now.
+    pub fn saving(&self) -> f64 {
+        self.now.average - self.best.average
+    }
+
+    pub fn saving_percent(&self) -> f64 {
+        if self.now.average > 0.0 {
+            self.saving() / self.now.average * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Finds the contiguous run of periods, long enough to cover the job and
+/// finishing before its deadline, with the lowest average forecast intensity.
+/// Also returns the window that starts in the current period, for comparison.
+///
+/// Windows are aligned to period boundaries and any that contain a period
+/// without a forecast, or a gap between periods, are skipped. Ties go to the
+/// earliest window.
+pub fn greenest_window(periods: &[Period], now: DateTime<Utc>, job: &Job) -> Option<Schedule> {
+    let duration = Duration::minutes(job.duration_minutes);
+    if duration <= Duration::zero() {
+        return None;
+    }
+
+    let first = periods.iter().position(|p| p.to > now)?;
+    let mut windows =
+        (first..periods.len()).filter_map(|start| window(&periods[start..], duration));
+
+    let current = windows.next().filter(|w| w.from <= now)?;
+    if current.to > job.deadline {
+        return None;
+    }
+    let best = windows
+        .take_while(|w| w.to <= job.deadline)
+        .fold(current.clone(), |best, w| {
+            if w.average < best.average {
+                w
+            } else {
+                best
+            }
+        });
+
+    Some(Schedule {
+        uncertain: horizon::is_uncertain(best.to, now),
+        best,
+        now: current,
+    })
+}
+
+/// The window of (at least) `duration` that starts with the first period,
+/// if the periods are contiguous and all have a forecast
+pub(super) fn window(periods: &[Period], duration: Duration) -> Option<Window> {
+    let from = periods.first()?.from;
+    let mut to = from;
+    let mut total = 0;
+    let mut count = 0;
+
+    for period in periods {
+        if to - from >= duration {
+            break;
+        }
+        if period.from != to {
+            return None;
+        }
+        total += period.intensity.as_ref()?.forecast;
+        count += 1;
+        to = period.to;
+    }
+
+    if to - from < duration {
+        return None;
+    }
+
+    Some(Window {
+        from,
+        to,
+        average: f64::from(total) / f64::from(count),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::periods;
+    use chrono::TimeZone;
+
+    #[test]
+    fn finds_lowest_average_window_before_deadline() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let periods = periods(start, &[200, 180, 120, 100, 110, 150, 40]);
+        let job = Job {
+            duration_minutes: 60,
+            deadline: start + Duration::hours(3),
+        };
+
+        let schedule = greenest_window(&periods, start + Duration::minutes(10), &job).unwrap();
+
+        assert_eq!(
+            schedule.now,
+            Window {
+                from: start,
+                to: start + Duration::hours(1),
+                average: 190.0,
+            }
+        );
+        assert_eq!(
+            schedule.best,
+            Window {
+                from: start + Duration::minutes(90),
+                to: start + Duration::minutes(150),
+                average: 105.0,
+            }
+        );
+        assert_eq!(schedule.saving(), 85.0);
+    }
+
+    #[test]
+    fn rounds_duration_up_to_whole_periods() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let periods = periods(start, &[100, 300, 50, 50, 300]);
+        let job = Job {
+            duration_minutes: 45,
+            deadline: start + Duration::hours(3),
+        };
+
+        let schedule = greenest_window(&periods, start, &job).unwrap();
+
+        assert_eq!(schedule.best.from, start + Duration::hours(1));
+        assert_eq!(schedule.best.to, start + Duration::hours(2));
+    }
+
+    #[test]
+    fn skips_windows_with_missing_forecasts() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let mut periods = periods(start, &[200, 10, 10, 150]);
+        periods[2].intensity = None;
+        let job = Job {
+            duration_minutes: 30,
+            deadline: start + Duration::hours(2),
+        };
+
+        let schedule = greenest_window(&periods, start, &job).unwrap();
+
+        assert_eq!(schedule.best.from, start + Duration::minutes(30));
+
+        let job = Job {
+            duration_minutes: 60,
+            ..job
+        };
+        let schedule = greenest_window(&periods, start, &job).unwrap();
+
+        assert_eq!(schedule.best, schedule.now);
+    }
+
+    #[test]
+    fn best_window_beyond_24_hours_is_uncertain() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let mut forecasts = vec![200; 96];
+        forecasts[60] = 50;
+        let periods = periods(start, &forecasts);
+        let job = Job {
+            duration_minutes: 30,
+            deadline: start + Duration::days(2),
+        };
+
+        let schedule = greenest_window(&periods, start, &job).unwrap();
+
+        assert_eq!(schedule.best.from, start + Duration::hours(30));
+        assert!(schedule.uncertain);
+
+        let job = Job {
+            deadline: start + Duration::days(1),
+            ..job
+        };
+        assert!(!greenest_window(&periods, start, &job).unwrap().uncertain);
+    }
+
+    #[test]
+    fn parse_jobs() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let parse = |minutes, deadline| Job::parse(minutes, deadline, now, Horizon::Hours24);
+
+        assert_eq!(
+            parse(60, "2023-07-06T22:00:00+01:00"),
+            Ok(Job {
+                duration_minutes: 60,
+                deadline: now + Duration::hours(1),
+            })
+        );
+        assert_eq!(
+            parse(0, "2023-07-07T07:00:00Z"),
+            Err(Error::InvalidDuration(0))
+        );
+        assert_eq!(
+            parse(i64::MAX, "2023-07-07T07:00:00Z"),
+            Err(Error::InvalidDuration(i64::MAX))
+        );
+        assert!(Job::parse(25 * 60, "2023-07-08T07:00:00Z", now, Horizon::Hours48).is_ok());
+        assert_eq!(
+            parse(25 * 60, "2023-07-08T07:00:00Z"),
+            Err(Error::InvalidDuration(25 * 60))
+        );
+        assert_eq!(
+            parse(90, "2023-07-06T21:00:00Z"),
+            Err(Error::DeadlineTooSoon("2023-07-06T21:00:00Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn no_window_if_job_cannot_finish_by_deadline() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 20, 0, 0).unwrap();
+        let periods = periods(start, &[100, 100, 100]);
+        let job = Job {
+            duration_minutes: 90,
+            deadline: start + Duration::hours(1),
+        };
+
+        assert_eq!(greenest_window(&periods, start, &job), None);
+    }
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{
+    error::Error,
+    horizon::Horizon,
+    intensity::Period,
+    postcode::Search,
+    regional::{self, DNO_REGIONS},
+};
+
+/// A place to compare, entered as a postcode, an outcode or a DNO region ID
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Site {
+    Postcode(Search),
+    Region(i64),
+}
+
+impl Site {
+    /// Anything that's just digits is a region ID, otherwise it should be a
+    /// postcode or outcode
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = text.trim();
+        if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+            return match text.parse() {
+                Ok(id) if DNO_REGIONS.contains(&id) => Ok(Site::Region(id)),
+                _ => Err(Error::InvalidRegion(text.to_string())),
+            };
+        }
+        Search::parse(text).map(Site::Postcode)
+    }
+
+    /// The URL of the site's regional forecast from `from`
+    pub fn url(&self, from: &DateTime<Utc>, horizon: Horizon) -> Url {
+        match self {
+            Site::Postcode(search) => regional::url(from, search.outcode(), horizon),
+            Site::Region(region_id) => regional::region_url(from, *region_id, horizon),
+        }
+    }
+}
+
+/// Checks the load (kW) to compare the sites' emissions of isn't negative
+/// or not a number
+pub fn check_load(load_kw: f64) -> Result<f64, Error> {
+    if load_kw.is_finite() && load_kw >= 0.0 {
+        Ok(load_kw)
+    } else {
+        Err(Error::InvalidEnergy(load_kw.to_string()))
+    }
+}
+
+/// A site and, once fetched, its regional forecast
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SiteForecast {
+    pub site: Site,
+    /// The short name of the site's region, e.g. "South England"
+    pub region: Option<String>,
+    pub periods: Vec<Period>,
+}
+
+impl SiteForecast {
+    pub fn new(site: Site) -> Self {
+        Self {
+            site,
+            region: None,
+            periods: vec![],
+        }
+    }
+
+    /// e.g. "KT1 (South England)" or, for a region ID, "South England"
+    pub fn name(&self) -> String {
+        match (&self.site, &self.region) {
+            (Site::Postcode(search), Some(region)) => format!("{search} ({region})"),
+            (Site::Postcode(search), None) => search.to_string(),
+            (Site::Region(_), Some(region)) => region.clone(),
+            (Site::Region(region_id), None) => format!("Region {region_id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sites() {
+        assert_eq!(Site::parse(" 13 "), Ok(Site::Region(13)));
+        assert_eq!(
+            Site::parse("kt1 2ud"),
+            Ok(Site::Postcode(Search::Postcode("KT1 2UD".to_string())))
+        );
+        assert_eq!(
+            Site::parse("15"),
+            Err(Error::InvalidRegion("15".to_string()))
+        );
+        assert_eq!(Site::parse("0"), Err(Error::InvalidRegion("0".to_string())));
+        assert_eq!(
+            Site::parse("KT1-2UD"),
+            Err(Error::InvalidPostcode("KT1-2UD".to_string()))
+        );
+    }
+
+    #[test]
+    fn check_loads() {
+        assert_eq!(check_load(2.5), Ok(2.5));
+        assert_eq!(check_load(0.0), Ok(0.0));
+        assert_eq!(
+            check_load(-1.0),
+            Err(Error::InvalidEnergy("-1".to_string()))
+        );
+        assert_eq!(
+            check_load(f64::NAN),
+            Err(Error::InvalidEnergy("NaN".to_string()))
+        );
+    }
+
+    #[test]
+    fn site_urls() {
+        let from = DateTime::parse_from_rfc3339("2023-07-06T20:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            Site::parse("KT1 2UD")
+                .unwrap()
+                .url(&from, Horizon::Hours24)
+                .as_str(),
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/fw24h/postcode/KT1"
+        );
+        assert_eq!(
+            Site::Region(13).url(&from, Horizon::Hours48).as_str(),
+            "https://api.carbonintensity.org.uk/regional/intensity/2023-07-06T20:30Z/fw48h/regionid/13"
+        );
+    }
+
+    #[test]
+    fn site_names() {
+        let mut forecast = SiteForecast::new(Site::parse("KT1").unwrap());
+        assert_eq!(forecast.name(), "KT1");
+        forecast.region = Some("South England".to_string());
+        assert_eq!(forecast.name(), "KT1 (South England)");
+
+        let mut forecast = SiteForecast::new(Site::Region(13));
+        assert_eq!(forecast.name(), "Region 13");
+        forecast.region = Some("London".to_string());
+        assert_eq!(forecast.name(), "London");
+    }
+}
@@ -0,0 +1,92 @@
+use serde_json::Value;
+
+use super::{error::Error, Model};
+
+/// The key the model is stored under
+pub const KEY: &str = "model";
+
+/// Bump this whenever a change to `Model` means that a previously stored
+/// model won't deserialize, and add a migration from the old version.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Migrations from each schema version to the next, so `MIGRATIONS[0]` takes
+/// a version 1 model to version 2, and so on. They work on the JSON rather
+/// than `Model`, because the old shape of the model no longer exists.
+const MIGRATIONS: &[fn(Value) -> Value] = &[];
+
+pub fn save(model: &Model) -> Vec<u8> {
+    let stored = serde_json::json!({
+        "version": SCHEMA_VERSION,
+        "model": model,
+    });
+    serde_json::to_vec(&stored).expect("model should serialize")
+}
+
+/// Restores a stored model, migrating it to the current schema version if
+/// it was stored by an older version of the app.
+pub fn load(bytes: &[u8]) -> Result<Model, Error> {
+    let mut stored: Value =
+        serde_json::from_slice(bytes).map_err(|e| Error::Decode(e.to_string()))?;
+
+    let version = stored["version"]
+        .as_u64()
+        .ok_or_else(|| Error::Decode("stored model has no version".to_string()))?;
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(Error::Decode(format!(
+            "cannot load model with schema version {version}"
+        )));
+    }
+
+    let mut model = stored["model"].take();
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        model = migration(model);
+    }
+
+    serde_json::from_value(model).map_err(|e| Error::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Mode, Place, SavedLocation};
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn round_trip() {
+        let model = Model {
+            mode: Mode::Local,
+            place: Place::Saved("Home".to_string()),
+            saved: vec![SavedLocation {
+                name: "Home".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(load(&save(&model)), Ok(model));
+    }
+
+    #[test]
+    fn errors_are_not_stored() {
+        let mut model = Model::default();
+        model.national.error = Some(Error::Status(503));
+
+        assert_eq!(load(&save(&model)).unwrap().national.error, None);
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let bytes = format!(r#"{{ "version": {}, "model": {{}} }}"#, SCHEMA_VERSION + 1);
+
+        assert_matches!(load(bytes.as_bytes()), Err(Error::Decode(_)));
+    }
+
+    #[test]
+    fn reject_unversioned_model() {
+        let bytes = save(&Model::default());
+        let mut stored: Value = serde_json::from_slice(&bytes).unwrap();
+        let bytes = serde_json::to_vec(&stored["model"].take()).unwrap();
+
+        assert_matches!(load(&bytes), Err(Error::Decode(_)));
+    }
+}
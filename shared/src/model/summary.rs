@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    fuel::Fuel,
+    intensity::Period,
+    scheduling::{self, Window},
+};
+
+/// The next 24 hours of a forecast at a glance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// The mean forecast intensity (gCO2/kWh)
+    pub average: f64,
+    /// The 2-hour window with the lowest forecast
+    pub best: Window,
+    /// The 2-hour window with the highest forecast
+    pub worst: Window,
+    /// The fuel with the largest mean share of the generation mix
+    pub dominant_fuel: Option<Fuel>,
+    /// The mean share of the generation mix from renewables
+    pub renewables_percent: Option<f64>,
+    /// The mean intensity (gCO2/kWh) over the 24 hours before
+    pub yesterday_average: Option<f64>,
+}
+
+impl Summary {
+    /// How much higher (or lower, if negative) the average is than yesterday's
+    pub fn change_from_yesterday_percent(&self) -> Option<f64> {
+        self.yesterday_average
+            .filter(|yesterday| *yesterday > 0.0)
+            .map(|yesterday| (self.average - yesterday) / yesterday * 100.0)
+    }
+}
+
+/// Summarises the 24 hours of `forecast` from the current period. The
+/// generation mix comes from `mix`, which may be the forecast itself, and
+/// yesterday's average from any periods in `past` (using actuals where
+/// there are any). Returns `None` unless there's at least one 2-hour window.
+pub fn summary(
+    forecast: &[Period],
+    mix: &[Period],
+    past: &[Period],
+    now: DateTime<Utc>,
+) -> Option<Summary> {
+    let start = forecast.iter().position(|period| period.to > now)?;
+    let from = forecast[start].from;
+    let to = from + Duration::days(1);
+    let day: Vec<Period> = forecast[start..]
+        .iter()
+        .take_while(|period| period.to <= to)
+        .cloned()
+        .collect();
+
+    let mut windows =
+        (0..day.len()).filter_map(|i| scheduling::window(&day[i..], Duration::hours(2)));
+    let first = windows.next()?;
+    let (best, worst) = windows.fold((first.clone(), first), |(best, worst), w| {
+        let best = if w.average < best.average {
+            w.clone()
+        } else {
+            best
+        };
+        let worst = if w.average > worst.average { w } else { worst };
+        (best, worst)
+    });
+
+    let forecasts: Vec<f64> = day
+        .iter()
+        .filter_map(|period| period.intensity.as_ref())
+        .map(|intensity| f64::from(intensity.forecast))
+        .collect();
+    let yesterday: Vec<f64> = past
+        .iter()
+        .filter(|period| period.from >= from - Duration::days(1) && period.to <= from)
+        .filter_map(|period| period.intensity.as_ref())
+        .map(|intensity| f64::from(intensity.actual.unwrap_or(intensity.forecast)))
+        .collect();
+
+    let shares = fuel_shares(mix, from, to);
+    let dominant_fuel = shares
+        .iter()
+        .max_by(|(a_fuel, a), (b_fuel, b)| a.total_cmp(b).then_with(|| b_fuel.cmp(a_fuel)))
+        .map(|(fuel, _)| fuel.clone());
+    let renewables_percent = (!shares.is_empty()).then(|| {
+        shares
+            .iter()
+            .filter(|(fuel, _)| fuel.is_renewable())
+            .map(|(_, share)| share)
+            .sum()
+    });
+
+    Some(Summary {
+        from,
+        to,
+        average: mean(&forecasts)?,
+        best,
+        worst,
+        dominant_fuel,
+        renewables_percent,
+        yesterday_average: mean(&yesterday),
+    })
+}
+
+/// The mean percentage of each fuel across the periods between two times
+/// that have a generation mix
+fn fuel_shares(mix: &[Period], from: DateTime<Utc>, to: DateTime<Utc>) -> HashMap<Fuel, f64> {
+    let mixes: Vec<_> = mix
+        .iter()
+        .filter(|period| period.from >= from && period.to <= to)
+        .filter_map(|period| period.generation_mix.as_ref())
+        .collect();
+
+    let mut shares = HashMap::new();
+    for mix in &mixes {
+        for fuel in mix.iter() {
+            *shares.entry(fuel.fuel.clone()).or_insert(0.0) +=
+                f64::from(fuel.percentage) / mixes.len() as f64;
+        }
+    }
+    shares
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::{periods, ApiName, GenerationMix};
+    use chrono::TimeZone;
+
+    fn mix(fuels: &[(&str, f32)]) -> Option<Vec<GenerationMix>> {
+        Some(
+            fuels
+                .iter()
+                .map(|&(fuel, percentage)| GenerationMix {
+                    fuel: Fuel::from_name(fuel),
+                    percentage,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn best_and_worst_two_hour_windows() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let forecast = periods(
+            start,
+            &[150, 150, 150, 150, 200, 200, 200, 200, 50, 50, 50, 50],
+        );
+
+        let summary = summary(&forecast, &[], &[], start + Duration::minutes(10)).unwrap();
+
+        assert_eq!(summary.from, start);
+        assert_eq!(summary.to, start + Duration::days(1));
+        assert_eq!(summary.average, 1600.0 / 12.0);
+        assert_eq!(summary.best.from, start + Duration::hours(4));
+        assert_eq!(summary.best.average, 50.0);
+        assert_eq!(summary.worst.from, start + Duration::hours(2));
+        assert_eq!(summary.worst.average, 200.0);
+    }
+
+    #[test]
+    fn only_the_next_24_hours() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let mut forecast = periods(start, &[100; 48]);
+        forecast.extend(periods(start + Duration::days(1), &[500; 4]));
+
+        let summary = summary(&forecast, &[], &[], start).unwrap();
+
+        assert_eq!(summary.average, 100.0);
+        assert_eq!(summary.worst.average, 100.0);
+    }
+
+    #[test]
+    fn no_summary_without_a_two_hour_window() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let forecast = periods(start, &[100, 100, 100]);
+
+        assert_eq!(summary(&forecast, &[], &[], start), None);
+    }
+
+    #[test]
+    fn mix_and_comparison_to_yesterday() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let mut forecast = periods(start, &[150, 150, 150, 150]);
+        forecast[0].generation_mix = mix(&[("gas", 40.0), ("wind", 30.0), ("solar", 10.0)]);
+        forecast[1].generation_mix = mix(&[("gas", 30.0), ("wind", 50.0), ("solar", 10.0)]);
+        let mut past = periods(start - Duration::hours(1), &[100, 100]);
+        past[1].intensity.as_mut().unwrap().actual = Some(140);
+
+        let summary = summary(&forecast, &forecast, &past, start).unwrap();
+
+        assert_eq!(summary.dominant_fuel, Some(Fuel::Wind));
+        assert_eq!(summary.renewables_percent, Some(40.0 + 10.0));
+        assert_eq!(summary.yesterday_average, Some(120.0));
+        assert_eq!(summary.change_from_yesterday_percent(), Some(25.0));
+    }
+}
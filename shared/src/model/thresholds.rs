@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::Error,
+    intensity::{Intensity, IntensityIndex, Period},
+    zone::Zone,
+    Mode,
+};
+
+/// The longest a threshold can warn the user in advance, which is a day
+pub const MAX_WARNING_MINUTES: i64 = 24 * 60;
+
+/// A rule for notifying the user about the forecast, e.g. "tell me when
+/// intensity in my region drops below 100 g"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Threshold {
+    /// Identifies the rule, so that it can be replaced or removed
+    pub name: String,
+    /// Which forecast to watch. `Local` (or `Compare`) is the selected place
+    /// and `Regions` the selected region.
+    pub scope: Mode,
+    pub condition: Condition,
+    /// How long before the period starts to notify the user
+    pub warning_minutes: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The forecast is below this many gCO2/kWh
+    Below(i32),
+    /// The forecast is above this many gCO2/kWh
+    Above(i32),
+    /// The forecast has this index, e.g. very high
+    Index(IntensityIndex),
+}
+
+impl Condition {
+    pub fn matches(&self, intensity: &Intensity) -> bool {
+        match self {
+            Condition::Below(limit) => intensity.forecast < *limit,
+            Condition::Above(limit) => intensity.forecast > *limit,
+            Condition::Index(index) => intensity.index == *index,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::Below(limit) => format!("below {limit} gCO2/kWh"),
+            Condition::Above(limit) => format!("above {limit} gCO2/kWh"),
+            Condition::Index(index) => index.to_string().to_lowercase(),
+        }
+    }
+}
+
+/// A period we've already notified the user about for a threshold, so that
+/// we don't do it again when the forecast is next refreshed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Notified {
+    pub threshold: String,
+    pub from: DateTime<Utc>,
+}
+
+impl Threshold {
+    /// Checks the warning is between zero and `MAX_WARNING_MINUTES`, so that
+    /// notifications come before the period starts
+    pub fn validate(&self) -> Result<(), Error> {
+        if (0..=MAX_WARNING_MINUTES).contains(&self.warning_minutes) {
+            Ok(())
+        } else {
+            Err(Error::InvalidWarning(self.warning_minutes))
+        }
+    }
+
+    /// The start of each upcoming period where the forecast starts to meet
+    /// the condition. Periods that carry on from a matching period (with no
+    /// gap between them) are part of the same run, so aren't included.
+    pub fn crossings(&self, periods: &[Period], now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let matches = |period: &Period| {
+            period
+                .intensity
+                .as_ref()
+                .map_or(false, |intensity| self.condition.matches(intensity))
+        };
+
+        let mut crossings = vec![];
+        let mut previous: Option<&Period> = None;
+        for period in periods {
+            let continues = previous.map_or(false, |p| p.to == period.from && matches(p));
+            if period.from > now && matches(period) && !continues {
+                crossings.push(period.from);
+            }
+            previous = Some(period);
+        }
+        crossings
+    }
+
+    /// When to notify the user about a period starting at `from`
+    pub fn notify_at(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        from - Duration::minutes(self.warning_minutes)
+    }
+
+    pub fn message(&self, from: DateTime<Utc>, zone: Zone) -> String {
+        format!(
+            "Carbon intensity is forecast to be {} from {} {}",
+            self.condition.describe(),
+            zone.local(from).format("%H:%M"),
+            zone.abbreviation(from)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::intensity::periods;
+    use chrono::TimeZone;
+
+    fn threshold(condition: Condition) -> Threshold {
+        Threshold {
+            name: "test".to_string(),
+            scope: Mode::Local,
+            condition,
+            warning_minutes: 0,
+        }
+    }
+
+    #[test]
+    fn start_of_each_run_below_limit() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let periods = periods(start, &[120, 90, 80, 130, 95]);
+
+        let crossings = threshold(Condition::Below(100)).crossings(&periods, start);
+
+        assert_eq!(
+            crossings,
+            vec![
+                start + Duration::minutes(30),
+                start + Duration::minutes(120)
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_runs_that_have_already_started() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let periods = periods(start, &[90, 80]);
+
+        let crossings =
+            threshold(Condition::Below(100)).crossings(&periods, start + Duration::minutes(10));
+
+        assert!(crossings.is_empty());
+    }
+
+    #[test]
+    fn match_index() {
+        let start = Utc.with_ymd_and_hms(2023, 7, 6, 0, 0, 0).unwrap();
+        let mut periods = periods(start, &[200, 320]);
+        periods[1].intensity.as_mut().unwrap().index = IntensityIndex::VeryHigh;
+        let threshold = Threshold {
+            warning_minutes: 60,
+            ..threshold(Condition::Index(IntensityIndex::VeryHigh))
+        };
+
+        let crossings = threshold.crossings(&periods, start);
+
+        assert_eq!(crossings, vec![start + Duration::minutes(30)]);
+        assert_eq!(
+            threshold.notify_at(crossings[0]),
+            start - Duration::minutes(30)
+        );
+        assert_eq!(
+            threshold.message(crossings[0], Zone::Utc),
+            "Carbon intensity is forecast to be very high from 00:30 UTC"
+        );
+        assert_eq!(
+            threshold.message(crossings[0], Zone::UnitedKingdom),
+            "Carbon intensity is forecast to be very high from 01:30 BST"
+        );
+    }
+}
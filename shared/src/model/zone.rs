@@ -0,0 +1,158 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The time zone to show times in, set by the shell with `Event::SetZone`
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Zone {
+    /// GMT in winter and BST (GMT+1) in summer
+    #[default]
+    UnitedKingdom,
+    Utc,
+}
+
+impl Zone {
+    pub fn offset(self, at: DateTime<Utc>) -> FixedOffset {
+        let hours = match self {
+            Zone::UnitedKingdom if is_summer_time(at) => 1,
+            Zone::UnitedKingdom | Zone::Utc => 0,
+        };
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+
+    /// e.g. "BST"
+    pub fn abbreviation(self, at: DateTime<Utc>) -> &'static str {
+        match self {
+            Zone::UnitedKingdom if is_summer_time(at) => "BST",
+            Zone::UnitedKingdom => "GMT",
+            Zone::Utc => "UTC",
+        }
+    }
+
+    pub fn local(self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        at.with_timezone(&self.offset(at))
+    }
+
+    /// The local date at a moment
+    pub fn date(self, at: DateTime<Utc>) -> NaiveDate {
+        self.local(at).date_naive()
+    }
+
+    /// When a local day starts. The clocks never change at midnight, so
+    /// there's always exactly one.
+    pub fn start_of_day(self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap());
+        // the offset at UTC midnight is the one at local midnight, unless
+        // the clocks change in between
+        let guess = midnight - Duration::seconds(self.offset(midnight).local_minus_utc().into());
+        midnight - Duration::seconds(self.offset(guess).local_minus_utc().into())
+    }
+
+    pub fn day(self, date: NaiveDate) -> Day {
+        Day {
+            date,
+            from: self.start_of_day(date),
+            to: self.start_of_day(date.succ_opt().unwrap()),
+        }
+    }
+
+    /// The local days that overlap the time between `from` and `to`
+    pub fn days(self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Day> {
+        let first = self.day(self.date(from));
+        std::iter::successors(Some(first), |day| {
+            day.date.succ_opt().map(|date| self.day(date))
+        })
+        .take_while(|day| from < to && day.from < to)
+        .collect()
+    }
+}
+
+/// A local day, which in the UK is 23 or 25 hours long on the days the
+/// clocks change
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl Day {
+    /// Usually 48, but 46 when the clocks go forward and 50 when they go back
+    pub fn half_hours(&self) -> i64 {
+        (self.to - self.from).num_minutes() / 30
+    }
+}
+
+/// British Summer Time runs from 01:00 UTC on the last Sunday in March to
+/// 01:00 UTC on the last Sunday in October
+fn is_summer_time(at: DateTime<Utc>) -> bool {
+    at >= last_sunday_1am(at.year(), 3) && at < last_sunday_1am(at.year(), 10)
+}
+
+fn last_sunday_1am(year: i32, month: u32) -> DateTime<Utc> {
+    let last = NaiveDate::from_ymd_opt(year, month, 31).unwrap();
+    let sunday = last - Duration::days(last.weekday().num_days_from_sunday().into());
+    Utc.from_utc_datetime(&sunday.and_hms_opt(1, 0, 0).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn summer_and_winter_time() {
+        let summer = Utc.with_ymd_and_hms(2023, 7, 6, 23, 30, 0).unwrap();
+        let winter = Utc.with_ymd_and_hms(2023, 12, 6, 23, 30, 0).unwrap();
+
+        assert_eq!(
+            Zone::UnitedKingdom.local(summer).to_rfc3339(),
+            "2023-07-07T00:30:00+01:00"
+        );
+        assert_eq!(Zone::UnitedKingdom.abbreviation(summer), "BST");
+        assert_eq!(Zone::UnitedKingdom.date(summer), date(2023, 7, 7));
+        assert_eq!(Zone::UnitedKingdom.abbreviation(winter), "GMT");
+        assert_eq!(Zone::UnitedKingdom.date(winter), date(2023, 12, 6));
+        assert_eq!(Zone::Utc.date(summer), date(2023, 7, 6));
+    }
+
+    #[test]
+    fn clocks_change_at_1am_utc() {
+        let spring = Utc.with_ymd_and_hms(2023, 3, 26, 1, 0, 0).unwrap();
+        let autumn = Utc.with_ymd_and_hms(2023, 10, 29, 1, 0, 0).unwrap();
+        let before = Duration::minutes(30);
+
+        assert_eq!(Zone::UnitedKingdom.abbreviation(spring - before), "GMT");
+        assert_eq!(Zone::UnitedKingdom.abbreviation(spring), "BST");
+        assert_eq!(Zone::UnitedKingdom.abbreviation(autumn - before), "BST");
+        assert_eq!(Zone::UnitedKingdom.abbreviation(autumn), "GMT");
+    }
+
+    #[test]
+    fn days_when_the_clocks_change() {
+        let zone = Zone::UnitedKingdom;
+
+        let summer = zone.day(date(2023, 7, 6));
+        assert_eq!(summer.from.to_rfc3339(), "2023-07-05T23:00:00+00:00");
+        assert_eq!(summer.half_hours(), 48);
+        assert_eq!(zone.day(date(2023, 3, 26)).half_hours(), 46);
+        assert_eq!(zone.day(date(2023, 10, 29)).half_hours(), 50);
+        assert_eq!(Zone::Utc.day(date(2023, 10, 29)).half_hours(), 48);
+    }
+
+    #[test]
+    fn days_overlapping_a_range() {
+        let from = Utc.with_ymd_and_hms(2023, 10, 28, 22, 0, 0).unwrap();
+
+        let days = Zone::UnitedKingdom.days(from, from + Duration::hours(48));
+
+        let dates: Vec<_> = days.iter().map(|day| day.date).collect();
+        assert_eq!(
+            dates,
+            vec![date(2023, 10, 28), date(2023, 10, 29), date(2023, 10, 30)]
+        );
+        assert!(Zone::UnitedKingdom.days(from, from).is_empty());
+    }
+}
@@ -1,21 +1,89 @@
-use std::collections::HashMap;
-
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::model::{
-    intensity::{GenerationMix, Period},
-    Mode, Model,
+    accuracy::{self, Accuracy, PeriodError},
+    comparison::{self, Difference},
+    factors::Factors,
+    footprint::{self, Emissions, Footprint},
+    freshness::Freshness,
+    fuel::{Categories, Fuel},
+    history::HistoryRange,
+    horizon::{self, Horizon},
+    intensity::{self, GenerationMix, IntensityIndex, MixQuality, Period},
+    location::Location,
+    regional::Region,
+    scheduling::{self, Schedule, Window},
+    sites::SiteForecast,
+    summary::{self, Summary},
+    thresholds::Threshold,
+    zone::{Day, Zone},
+    Data, DataSet, Mode, Model, Place, SavedLocation,
 };
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ViewModel {
     pub mode: Mode,
+    pub horizon: Horizon,
+    pub zone: Zone,
+    /// The current time in the zone, e.g. "14:35 BST"
+    pub now_label: String,
+    /// The local days the current mode's forecast covers
+    pub days: Vec<DayPoint>,
     pub national_name: String,
     pub national_intensity: Vec<IntensityPoint>,
     pub national_mix: Vec<GenerationMixPoint>,
+    pub national_mix_categories: Vec<MixCategoriesPoint>,
+    pub national_mix_average: Option<MixCategories>,
+    pub national_error: Option<String>,
+    pub national_accuracy: Option<ForecastAccuracy>,
+    pub national_freshness: Freshness,
+    pub national_as_of: Option<String>,
+    pub national_current: Option<IntensityPoint>,
+    pub national_loading: bool,
     pub local_name: String,
     pub local_intensity: Vec<IntensityPoint>,
     pub local_mix: Vec<GenerationMixPoint>,
+    pub local_mix_categories: Vec<MixCategoriesPoint>,
+    pub local_mix_average: Option<MixCategories>,
+    pub local_error: Option<String>,
+    pub local_accuracy: Option<ForecastAccuracy>,
+    pub local_freshness: Freshness,
+    pub local_as_of: Option<String>,
+    pub local_current: Option<IntensityPoint>,
+    pub local_loading: bool,
+    pub saved_locations: Vec<SavedLocationPoint>,
+    pub regions: Vec<RegionPoint>,
+    pub region_name: String,
+    pub region_intensity: Vec<IntensityPoint>,
+    pub region_mix: Vec<GenerationMixPoint>,
+    pub region_mix_categories: Vec<MixCategoriesPoint>,
+    pub region_mix_average: Option<MixCategories>,
+    pub region_error: Option<String>,
+    pub region_accuracy: Option<ForecastAccuracy>,
+    pub region_freshness: Freshness,
+    pub region_as_of: Option<String>,
+    pub region_current: Option<IntensityPoint>,
+    pub region_loading: bool,
+    pub history_name: String,
+    pub history_intensity: Vec<IntensityPoint>,
+    pub history_error: Option<String>,
+    pub history_accuracy: Option<ForecastAccuracy>,
+    pub history_loading: bool,
+    pub greenest_window: Option<GreenestWindow>,
+    pub job_error: Option<String>,
+    pub footprint: Option<CarbonFootprint>,
+    pub footprint_error: Option<String>,
+    pub thresholds: Vec<ThresholdPoint>,
+    pub threshold_error: Option<String>,
+    pub daily_summary: Option<DailySummary>,
+    pub comparison: Vec<ComparisonPoint>,
+    pub comparison_current: Option<ComparisonPoint>,
+    /// e.g. "Your region is 12% cleaner than the UK average right now"
+    pub comparison_headline: Option<String>,
+    pub site_comparison: Option<SiteComparison>,
+    pub sites_error: Option<String>,
+    pub sites_loading: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,105 +91,365 @@ pub struct IntensityPoint {
     pub date: String,
     pub forecast: i32,
     pub actual: Option<i32>,
+    /// The intensity derived from the period's generation mix and the
+    /// emission factors, to cross-check the forecast
+    pub derived: Option<i32>,
+    pub mix_quality: MixQuality,
+    /// The period is more than 24 hours ahead, so its forecast is less
+    /// certain
+    pub uncertain: bool,
+    /// The start of the period in the zone, e.g. "14:30"
+    pub label: String,
+    /// The period starts at local midnight
+    pub starts_day: bool,
+    /// The period is the current one
+    pub now: bool,
+}
+
+/// A local day, which has 46 or 50 half-hours when the clocks change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayPoint {
+    /// e.g. "2023-07-06"
+    pub date: String,
+    /// e.g. "Thu 6 Jul"
+    pub label: String,
+    pub from: String,
+    pub to: String,
+    pub half_hours: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLocationPoint {
+    pub name: String,
+    pub area: Option<String>,
+    pub forecast: Option<i32>,
+    pub index: Option<IntensityIndex>,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionPoint {
+    pub id: i64,
+    pub name: String,
+    pub dno_region: String,
+    pub forecast: Option<i32>,
+    pub index: Option<IntensityIndex>,
+    pub selected: bool,
+}
+
+/// The next 24 hours of the forecast for the current mode, e.g. for a
+/// widget or a morning notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySummary {
+    pub from: String,
+    pub to: String,
+    pub average: f64,
+    pub best: IntensityWindow,
+    pub worst: IntensityWindow,
+    pub dominant_fuel: Option<Fuel>,
+    pub renewables_percent: Option<f64>,
+    pub yesterday_average: Option<f64>,
+    pub change_from_yesterday_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntensityWindow {
+    pub from: String,
+    pub to: String,
+    pub average: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdPoint {
+    pub name: String,
+    pub scope: Mode,
+    /// e.g. "below 100 gCO2/kWh"
+    pub condition: String,
+    pub warning_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreenestWindow {
+    pub from: String,
+    pub to: String,
+    pub forecast: f64,
+    pub run_now_forecast: f64,
+    pub saving: f64,
+    pub saving_percent: f64,
+    /// The window is more than 24 hours ahead, so its forecast is less
+    /// certain
+    pub uncertain: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarbonFootprint {
+    pub from: String,
+    pub to: String,
+    pub grams: f64,
+    pub best: FootprintWindow,
+    pub worst: FootprintWindow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootprintWindow {
+    pub from: String,
+    pub to: String,
+    pub grams: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastAccuracy {
+    pub mean_absolute_error: f64,
+    pub bias: f64,
+    pub mean_absolute_percentage_error: Option<f64>,
+    pub periods: Vec<ForecastErrorPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastErrorPoint {
+    pub date: String,
+    pub error: i32,
+    pub percentage_error: Option<f64>,
+}
+
+/// The local and national forecasts for a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonPoint {
+    pub date: String,
+    pub local: i32,
+    pub national: i32,
+    /// Local minus national (gCO2/kWh)
+    pub difference: i32,
+    /// How much cleaner local is than national, or negative if it's dirtier
+    pub cleaner_percent: Option<f64>,
+}
+
+/// The forecasts for two places the user has chosen, and the difference in
+/// the emissions of a constant load between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteComparison {
+    pub first_name: String,
+    pub second_name: String,
+    pub load_kw: f64,
+    pub periods: Vec<SiteComparisonPoint>,
+    /// Grams of CO2 the load emits in the first place over all the periods,
+    /// less what it emits in the second
+    pub total_grams: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteComparisonPoint {
+    pub date: String,
+    pub first: i32,
+    pub second: i32,
+    /// First minus second (gCO2/kWh)
+    pub difference: i32,
+    /// How much cleaner the first is than the second, or negative if it's
+    /// dirtier
+    pub cleaner_percent: Option<f64>,
+    /// The difference in emissions of the load up to the end of the period
+    pub cumulative_grams: f64,
+}
+
+/// A period's generation mix in broad categories of fuel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixCategoriesPoint {
+    pub date: String,
+    pub categories: MixCategories,
+}
+
+/// Percentages of the generation mix. Low carbon includes the renewables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixCategories {
+    pub renewable: f64,
+    pub low_carbon: f64,
+    pub fossil: f64,
+    pub imports: f64,
+    pub other: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationMixPoint {
     pub date: String,
-    pub fuel: String,
+    pub fuel: Fuel,
+    /// The fuel's name for legends, e.g. "Coal"
+    pub label: String,
     #[serde(rename = "perc")]
     pub percentage: f32,
+    /// The fuel's contribution to the intensity (gCO2/kWh)
+    pub grams: f64,
 }
 
 impl From<&Model> for ViewModel {
     fn from(value: &Model) -> Self {
-        let mix_order: HashMap<String, u8> = [
-            "Coal", "Gas", "Imports", "Biomass", "Nuclear", "Hydro", "Wind", "Solar",
-        ]
-        .iter()
-        .cloned()
-        .enumerate()
-        .map(|(i, s)| (s.to_string(), i as u8))
-        .collect();
+        let factors = value.factors();
+        let national_mix_periods = upcoming(&value.national.scope.generation_mix, value.time);
+        let national_mix = generation_mix(&national_mix_periods, &factors);
+        let (national_mix_categories, national_mix_average) = mix_categories(&national_mix_periods);
 
-        let mut national_mix: Vec<GenerationMixPoint> = value
-            .national
+        let national_intensity = intensity_points(
+            &upcoming(&value.national.periods, value.time),
+            value.time,
+            value.zone,
+            &factors,
+        );
+
+        let local = value.selected();
+        let local_name = area(&local.scope.location).unwrap_or_else(|| "Local".to_string());
+
+        let local_periods = upcoming(&local.periods, value.time);
+        let local_intensity = intensity_points(&local_periods, value.time, value.zone, &factors);
+
+        let local_mix = generation_mix(&local_periods, &factors);
+        let (local_mix_categories, local_mix_average) = mix_categories(&local_periods);
+
+        let regions = &value.regions;
+        let region = regions
             .scope
-            .generation_mix
-            .clone()
-            .into_iter()
-            .flat_map(|period| {
-                period
-                    .generation_mix
-                    .clone()
-                    .into_iter()
-                    .flat_map(move |mix| {
-                        let period = period.clone();
-                        mix.clone()
-                            .into_iter()
-                            .map(move |mix| GenerationMixPoint::from((period.clone(), mix)))
-                    })
-            })
-            .filter(|m| m.fuel != "Other")
-            .collect();
-        national_mix.sort_by(|a, b| mix_order[&a.fuel].cmp(&mix_order[&b.fuel]));
-
-        let national_intensity = value
-            .national
-            .periods
-            .clone()
-            .into_iter()
-            .map(IntensityPoint::from)
-            .collect();
-
-        let location = value.local.scope.location.clone();
-        let local_name = if location.is_some() {
-            format!(
-                "{area}, {code}",
-                area = location.clone().unwrap().admin_district,
-                code = location.unwrap().outcode,
-            )
-        } else {
-            "Local".to_string()
+            .regions
+            .iter()
+            .find(|region| Some(region.region_id) == regions.scope.selected);
+        let region_name = region
+            .map(|region| region.shortname.clone())
+            .unwrap_or_else(|| "Regions".to_string());
+        let region_periods = upcoming(&regions.periods, value.time);
+        let region_intensity = intensity_points(&region_periods, value.time, value.zone, &factors);
+        let region_mix = generation_mix(&region_periods, &factors);
+        let (region_mix_categories, region_mix_average) = mix_categories(&region_periods);
+
+        let periods = match value.mode {
+            Mode::National => &value.national.periods,
+            Mode::Local | Mode::Compare => &local.periods,
+            Mode::Regions => &regions.periods,
+        };
+        let (mix, past) = match value.mode {
+            Mode::National => (&value.national.scope.generation_mix, &value.national.past),
+            Mode::Local | Mode::Compare => (periods, &local.past),
+            Mode::Regions => (periods, &regions.past),
+        };
+        let comparison = comparison::compare(&local_periods, &value.national.periods);
+        let comparison_current = comparison
+            .iter()
+            .find(|difference| difference.from <= value.time && value.time < difference.to)
+            .cloned();
+        let comparison_headline = comparison_current.as_ref().and_then(headline);
+
+        let site_comparison = match value.sites.scope.forecasts.as_slice() {
+            [first, second] => Some(site_comparison(
+                first,
+                second,
+                value.sites.scope.load_kw,
+                value.time,
+            )),
+            _ => None,
+        };
+
+        let upcoming_periods = upcoming(periods, value.time);
+        let days = match (upcoming_periods.first(), upcoming_periods.last()) {
+            (Some(first), Some(last)) => value.zone.days(first.from, last.to),
+            _ => vec![],
         };
 
-        let local_intensity = value
-            .local
-            .periods
-            .clone()
-            .into_iter()
-            .map(|p| IntensityPoint::from(p))
-            .collect();
-
-        let mut local_mix: Vec<GenerationMixPoint> = value
-            .local
-            .periods
-            .clone()
-            .into_iter()
-            .flat_map(|period| {
-                period
-                    .generation_mix
-                    .clone()
-                    .into_iter()
-                    .flat_map(move |mix| {
-                        let period = period.clone();
-                        mix.clone()
-                            .into_iter()
-                            .map(move |mix| GenerationMixPoint::from((period.clone(), mix)))
-                    })
-            })
-            .filter(|m| m.fuel != "Other")
-            .collect();
-        local_mix.sort_by(|a, b| mix_order[&a.fuel].cmp(&mix_order[&b.fuel]));
+        let daily_summary =
+            summary::summary(periods, mix, past, value.time).map(DailySummary::from);
+        let greenest_window = value
+            .job
+            .as_ref()
+            .and_then(|job| scheduling::greenest_window(periods, value.time, job))
+            .map(GreenestWindow::from);
+
+        // usage in the past may only be covered by the history
+        let footprint = value.usage.as_ref().and_then(|usage| {
+            footprint::footprint(periods, usage, value.zone)
+                .or_else(|| footprint::footprint(&value.history.periods, usage, value.zone))
+                .map(CarbonFootprint::from)
+        });
 
         ViewModel {
             mode: value.mode,
+            horizon: value.horizon,
+            zone: value.zone,
+            now_label: format!(
+                "{} {}",
+                value.zone.local(value.time).format("%H:%M"),
+                value.zone.abbreviation(value.time)
+            ),
+            days: days.into_iter().map(DayPoint::from).collect(),
             national_name: "UK".to_string(),
             national_intensity,
             national_mix,
+            national_mix_categories,
+            national_mix_average,
+            national_error: value.national.error.as_ref().map(ToString::to_string),
+            national_accuracy: accuracy::accuracy(&value.national.periods).map(From::from),
+            national_freshness: value
+                .national
+                .freshness(value.time, &value.refresh, value.horizon),
+            national_as_of: as_of(&value.national),
+            national_current: current(&value.national.periods, value.time, value.zone, &factors),
+            national_loading: value.national.is_loading(),
             local_name,
             local_intensity,
             local_mix,
+            local_mix_categories,
+            local_mix_average,
+            local_error: local.error.as_ref().map(ToString::to_string),
+            local_accuracy: accuracy::accuracy(&local.periods).map(From::from),
+            local_freshness: local.freshness(value.time, &value.refresh, value.horizon),
+            local_as_of: as_of(local),
+            local_current: current(&local.periods, value.time, value.zone, &factors),
+            local_loading: local.is_loading(),
+            saved_locations: value
+                .saved
+                .iter()
+                .map(|saved| SavedLocationPoint::from((saved, value)))
+                .collect(),
+            regions: regions
+                .scope
+                .regions
+                .iter()
+                .map(|region| RegionPoint::from((region, regions.scope.selected)))
+                .collect(),
+            region_name,
+            region_intensity,
+            region_mix,
+            region_mix_categories,
+            region_mix_average,
+            region_error: regions.error.as_ref().map(ToString::to_string),
+            region_accuracy: accuracy::accuracy(&regions.periods).map(From::from),
+            region_freshness: regions.freshness(value.time, &value.refresh, value.horizon),
+            region_as_of: as_of(regions),
+            region_current: current(&regions.periods, value.time, value.zone, &factors),
+            region_loading: regions.is_loading(),
+            history_name: value
+                .history
+                .scope
+                .range
+                .as_ref()
+                .map(HistoryRange::name)
+                .unwrap_or_else(|| "History".to_string()),
+            history_intensity: intensity_points(
+                &value.history.periods,
+                value.time,
+                value.zone,
+                &factors,
+            ),
+            history_error: value.history.error.as_ref().map(ToString::to_string),
+            history_accuracy: accuracy::accuracy(&value.history.periods).map(From::from),
+            history_loading: value.history.is_loading(),
+            greenest_window,
+            job_error: value.job_error.as_ref().map(ToString::to_string),
+            footprint,
+            footprint_error: value.usage_error.as_ref().map(ToString::to_string),
+            thresholds: value.thresholds.iter().map(ThresholdPoint::from).collect(),
+            threshold_error: value.threshold_error.as_ref().map(ToString::to_string),
+            daily_summary,
+            comparison: comparison.into_iter().map(ComparisonPoint::from).collect(),
+            comparison_current: comparison_current.map(ComparisonPoint::from),
+            comparison_headline,
+            site_comparison,
+            sites_error: value.sites.error.as_ref().map(ToString::to_string),
+            sites_loading: value.sites.is_loading(),
         }
     }
 }
@@ -130,32 +458,337 @@ impl From<Period> for IntensityPoint {
     fn from(value: Period) -> Self {
         IntensityPoint {
             date: value.from.to_rfc3339(),
+            mix_quality: value.mix_quality(),
             forecast: value
                 .intensity
                 .clone()
                 .map(|f| f.forecast)
                 .unwrap_or_default(),
             actual: value.intensity.map(|f| f.actual).unwrap_or_default(),
+            derived: None,
+            uncertain: false,
+            label: String::new(),
+            starts_day: false,
+            now: false,
+        }
+    }
+}
+
+impl From<(&SavedLocation, &Model)> for SavedLocationPoint {
+    fn from((saved, model): (&SavedLocation, &Model)) -> Self {
+        let intensity = intensity::current(&saved.data.periods, model.time)
+            .and_then(|period| period.intensity.clone());
+        SavedLocationPoint {
+            name: saved.name.clone(),
+            area: area(&saved.data.scope.location),
+            forecast: intensity.as_ref().map(|i| i.forecast),
+            index: intensity.map(|i| i.index),
+            selected: model.place == Place::Saved(saved.name.clone()),
+        }
+    }
+}
+
+impl From<(&Region, Option<i64>)> for RegionPoint {
+    fn from((region, selected): (&Region, Option<i64>)) -> Self {
+        let intensity = region
+            .data
+            .first()
+            .and_then(|period| period.intensity.clone());
+        RegionPoint {
+            id: region.region_id,
+            name: region.shortname.clone(),
+            dno_region: region.dno_region.clone(),
+            forecast: intensity.as_ref().map(|i| i.forecast),
+            index: intensity.map(|i| i.index),
+            selected: selected == Some(region.region_id),
         }
     }
 }
 
-impl From<(Period, GenerationMix)> for GenerationMixPoint {
-    fn from(value: (Period, GenerationMix)) -> Self {
+impl From<Day> for DayPoint {
+    fn from(value: Day) -> Self {
+        DayPoint {
+            date: value.date.to_string(),
+            label: value.date.format("%a %-d %b").to_string(),
+            from: value.from.to_rfc3339(),
+            to: value.to.to_rfc3339(),
+            half_hours: value.half_hours(),
+        }
+    }
+}
+
+impl From<Summary> for DailySummary {
+    fn from(value: Summary) -> Self {
+        DailySummary {
+            from: value.from.to_rfc3339(),
+            to: value.to.to_rfc3339(),
+            average: value.average,
+            change_from_yesterday_percent: value.change_from_yesterday_percent(),
+            best: IntensityWindow::from(value.best),
+            worst: IntensityWindow::from(value.worst),
+            dominant_fuel: value.dominant_fuel,
+            renewables_percent: value.renewables_percent,
+            yesterday_average: value.yesterday_average,
+        }
+    }
+}
+
+impl From<Difference> for ComparisonPoint {
+    fn from(value: Difference) -> Self {
+        ComparisonPoint {
+            date: value.from.to_rfc3339(),
+            local: value.first,
+            national: value.second,
+            difference: value.difference(),
+            cleaner_percent: value.cleaner_percent(),
+        }
+    }
+}
+
+impl From<Categories> for MixCategories {
+    fn from(value: Categories) -> Self {
+        MixCategories {
+            renewable: value.renewable,
+            low_carbon: value.low_carbon,
+            fossil: value.fossil,
+            imports: value.imports,
+            other: value.other,
+        }
+    }
+}
+
+impl From<Window> for IntensityWindow {
+    fn from(value: Window) -> Self {
+        IntensityWindow {
+            from: value.from.to_rfc3339(),
+            to: value.to.to_rfc3339(),
+            average: value.average,
+        }
+    }
+}
+
+impl From<&Threshold> for ThresholdPoint {
+    fn from(value: &Threshold) -> Self {
+        ThresholdPoint {
+            name: value.name.clone(),
+            scope: value.scope,
+            condition: value.condition.describe(),
+            warning_minutes: value.warning_minutes,
+        }
+    }
+}
+
+impl From<Footprint> for CarbonFootprint {
+    fn from(value: Footprint) -> Self {
+        CarbonFootprint {
+            from: value.usage.from.to_rfc3339(),
+            to: value.usage.to.to_rfc3339(),
+            grams: value.usage.grams,
+            best: FootprintWindow::from(value.best),
+            worst: FootprintWindow::from(value.worst),
+        }
+    }
+}
+
+impl From<Emissions> for FootprintWindow {
+    fn from(value: Emissions) -> Self {
+        FootprintWindow {
+            from: value.from.to_rfc3339(),
+            to: value.to.to_rfc3339(),
+            grams: value.grams,
+        }
+    }
+}
+
+impl From<Accuracy> for ForecastAccuracy {
+    fn from(value: Accuracy) -> Self {
+        ForecastAccuracy {
+            mean_absolute_error: value.mean_absolute_error,
+            bias: value.bias,
+            mean_absolute_percentage_error: value.mean_absolute_percentage_error,
+            periods: value
+                .periods
+                .into_iter()
+                .map(ForecastErrorPoint::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<PeriodError> for ForecastErrorPoint {
+    fn from(value: PeriodError) -> Self {
+        ForecastErrorPoint {
+            date: value.from.to_rfc3339(),
+            error: value.error,
+            percentage_error: value.percentage_error,
+        }
+    }
+}
+
+impl From<Schedule> for GreenestWindow {
+    fn from(value: Schedule) -> Self {
+        GreenestWindow {
+            from: value.best.from.to_rfc3339(),
+            to: value.best.to.to_rfc3339(),
+            forecast: value.best.average,
+            run_now_forecast: value.now.average,
+            saving: value.saving(),
+            saving_percent: value.saving_percent(),
+            uncertain: value.uncertain,
+        }
+    }
+}
+
+impl From<(Period, GenerationMix, &Factors)> for GenerationMixPoint {
+    fn from(value: (Period, GenerationMix, &Factors)) -> Self {
         GenerationMixPoint {
             date: value.0.from.to_rfc3339(),
-            fuel: uppercase_first(&value.1.fuel),
+            fuel: value.1.fuel.clone(),
+            label: value.1.fuel.to_string(),
             percentage: value.1.percentage,
+            grams: value.2.contribution(&value.1),
         }
     }
 }
 
-fn uppercase_first(text: &str) -> String {
-    assert!(text.len() >= 2, "text must be at least 2 chars long");
-    text.chars()
-        .next()
-        .unwrap()
-        .to_ascii_uppercase()
-        .to_string()
-        + &text[1..]
+fn intensity_points(
+    periods: &[Period],
+    time: DateTime<Utc>,
+    zone: Zone,
+    factors: &Factors,
+) -> Vec<IntensityPoint> {
+    periods
+        .iter()
+        .map(|period| {
+            let mix = period.generation_mix.as_ref();
+            IntensityPoint {
+                derived: mix.map(|mix| factors.intensity(mix).round() as i32),
+                mix_quality: MixQuality::new(mix.map(Vec::as_slice)),
+                uncertain: horizon::is_uncertain(period.to, time),
+                label: zone.local(period.from).format("%H:%M").to_string(),
+                starts_day: zone.start_of_day(zone.date(period.from)) == period.from,
+                now: period.from <= time && time < period.to,
+                ..IntensityPoint::from(period.clone())
+            }
+        })
+        .collect()
+}
+
+fn generation_mix(periods: &[Period], factors: &Factors) -> Vec<GenerationMixPoint> {
+    let mut mix: Vec<GenerationMixPoint> = periods
+        .iter()
+        .flat_map(|period| {
+            period
+                .generation_mix
+                .clone()
+                .into_iter()
+                .flat_map(move |mix| {
+                    let period = period.clone();
+                    mix.into_iter()
+                        .map(move |mix| GenerationMixPoint::from((period.clone(), mix, factors)))
+                })
+        })
+        .filter(|m| m.fuel != Fuel::Other)
+        .collect();
+    mix.sort_by(|a, b| a.fuel.cmp(&b.fuel));
+    mix
+}
+
+/// The fuel categories of each period's generation mix, and their mean
+fn mix_categories(periods: &[Period]) -> (Vec<MixCategoriesPoint>, Option<MixCategories>) {
+    let categories: Vec<(&Period, Categories)> = periods
+        .iter()
+        .filter_map(|period| Some((period, Categories::new(period.generation_mix.as_ref()?))))
+        .collect();
+    let mean = Categories::mean(
+        &categories
+            .iter()
+            .map(|(_, categories)| categories.clone())
+            .collect::<Vec<_>>(),
+    );
+
+    let points = categories
+        .into_iter()
+        .map(|(period, categories)| MixCategoriesPoint {
+            date: period.from.to_rfc3339(),
+            categories: MixCategories::from(categories),
+        })
+        .collect();
+    (points, mean.map(MixCategories::from))
+}
+
+/// Periods that haven't fully elapsed
+fn upcoming(periods: &[Period], time: DateTime<Utc>) -> Vec<Period> {
+    periods
+        .iter()
+        .filter(|period| period.to > time)
+        .cloned()
+        .collect()
+}
+
+fn current(
+    periods: &[Period],
+    time: DateTime<Utc>,
+    zone: Zone,
+    factors: &Factors,
+) -> Option<IntensityPoint> {
+    let period = intensity::current(periods, time)?;
+    intensity_points(std::slice::from_ref(period), time, zone, factors).pop()
+}
+
+fn site_comparison(
+    first: &SiteForecast,
+    second: &SiteForecast,
+    load_kw: f64,
+    time: DateTime<Utc>,
+) -> SiteComparison {
+    let differences = comparison::compare(&upcoming(&first.periods, time), &second.periods);
+    let cumulative = comparison::cumulative_grams(&differences, load_kw);
+    let periods: Vec<_> = differences
+        .into_iter()
+        .zip(cumulative)
+        .map(|(difference, cumulative_grams)| SiteComparisonPoint {
+            date: difference.from.to_rfc3339(),
+            first: difference.first,
+            second: difference.second,
+            difference: difference.difference(),
+            cleaner_percent: difference.cleaner_percent(),
+            cumulative_grams,
+        })
+        .collect();
+
+    SiteComparison {
+        first_name: first.name(),
+        second_name: second.name(),
+        load_kw,
+        total_grams: periods.last().map_or(0.0, |point| point.cumulative_grams),
+        periods,
+    }
+}
+
+fn headline(difference: &Difference) -> Option<String> {
+    let percent = difference.cleaner_percent()?.round() as i64;
+    let comparison = match percent {
+        0 => "the same as".to_string(),
+        percent if percent > 0 => format!("{percent}% cleaner than"),
+        percent => format!("{}% dirtier than", -percent),
+    };
+    Some(format!(
+        "Your region is {comparison} the UK average right now"
+    ))
+}
+
+/// When the data was fetched, if it ever was
+fn as_of<T: DataSet + Serialize>(data: &Data<T>) -> Option<String> {
+    (data.last_updated != DateTime::<Utc>::default()).then(|| data.last_updated.to_rfc3339())
+}
+
+fn area(location: &Option<Location>) -> Option<String> {
+    location.as_ref().map(|location| {
+        format!(
+            "{area}, {code}",
+            area = location.admin_district,
+            code = location.outcode,
+        )
+    })
 }
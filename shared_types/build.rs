@@ -1,5 +1,5 @@
 use crux_core::typegen::TypeGen;
-use shared::{App, Mode};
+use shared::{App, Condition, Freshness, Fuel, Horizon, IntensityIndex, MixQuality, Mode, Zone};
 use std::path::PathBuf;
 
 fn main() {
@@ -9,9 +9,67 @@ fn main() {
 
     gen.register_app::<App>().expect("register");
 
-    gen.register_type_with_samples(vec![Mode::National, Mode::Local])
+    gen.register_type_with_samples(vec![
+        Mode::National,
+        Mode::Local,
+        Mode::Regions,
+        Mode::Compare,
+    ])
+    .expect("register samples");
+
+    gen.register_type_with_samples(vec![Horizon::Hours24, Horizon::Hours48])
+        .expect("register samples");
+
+    gen.register_type_with_samples(vec![Zone::UnitedKingdom, Zone::Utc])
         .expect("register samples");
 
+    gen.register_type_with_samples(vec![
+        Freshness::NeverLoaded,
+        Freshness::Fresh,
+        Freshness::Stale,
+        Freshness::Expired,
+    ])
+    .expect("register samples");
+
+    gen.register_type_with_samples(vec![
+        MixQuality::Valid,
+        MixQuality::Missing,
+        MixQuality::Inconsistent,
+    ])
+    .expect("register samples");
+
+    gen.register_type_with_samples(vec![
+        Condition::Below(0),
+        Condition::Above(0),
+        Condition::Index(IntensityIndex::Moderate),
+    ])
+    .expect("register samples");
+
+    gen.register_type_with_samples(vec![
+        Fuel::Coal,
+        Fuel::Oil,
+        Fuel::Gas,
+        Fuel::Imports,
+        Fuel::Biomass,
+        Fuel::Nuclear,
+        Fuel::Hydro,
+        Fuel::Wind,
+        Fuel::Solar,
+        Fuel::Other,
+        Fuel::Unknown(String::new()),
+    ])
+    .expect("register samples");
+
+    gen.register_type_with_samples(vec![
+        IntensityIndex::VeryLow,
+        IntensityIndex::Low,
+        IntensityIndex::Moderate,
+        IntensityIndex::High,
+        IntensityIndex::VeryHigh,
+        IntensityIndex::Unknown(String::new()),
+    ])
+    .expect("register samples");
+
     let output_root = PathBuf::from("./generated");
 
     gen.swift("SharedTypes", output_root.join("swift"))
@@ -0,0 +1,24 @@
+import {
+  KeyValueRequest,
+  KeyValueRequestVariantRead,
+  KeyValueRequestVariantWrite,
+  KeyValueResponse,
+  KeyValueResponseVariantRead,
+  KeyValueResponseVariantWrite,
+} from "shared_types/types/shared_types";
+
+export function keyValueRequest(request: KeyValueRequest): KeyValueResponse {
+  if (request instanceof KeyValueRequestVariantWrite) {
+    const value = request.field1
+      .map((byte) => String.fromCharCode(byte))
+      .join("");
+    localStorage.setItem(request.field0, btoa(value));
+    return new KeyValueResponseVariantWrite(true);
+  }
+
+  const key = (request as KeyValueRequestVariantRead).value;
+  const value = localStorage.getItem(key);
+  return new KeyValueResponseVariantRead(
+    value === null ? null : Array.from(atob(value), (c) => c.charCodeAt(0))
+  );
+}
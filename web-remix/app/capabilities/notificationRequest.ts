@@ -0,0 +1,32 @@
+import {
+  NotificationRequest,
+  NotificationRequestVariantCancel,
+  NotificationRequestVariantSchedule,
+} from "shared_types/types/shared_types";
+
+// the browser can't show notifications while the page is closed, so these
+// only fire while it's open
+const timers = new Map<string, ReturnType<typeof setTimeout>>();
+
+export function notificationRequest(request: NotificationRequest) {
+  if (request instanceof NotificationRequestVariantCancel) {
+    clearTimeout(timers.get(request.value));
+    timers.delete(request.value);
+    return;
+  }
+
+  const { id, title, body, at } = (
+    request as NotificationRequestVariantSchedule
+  ).value;
+  clearTimeout(timers.get(id));
+  const delay = Math.max(new Date(at).getTime() - Date.now(), 0);
+  timers.set(
+    id,
+    setTimeout(async () => {
+      timers.delete(id);
+      if ((await Notification.requestPermission()) === "granted") {
+        new Notification(title, { body });
+      }
+    }, delay)
+  );
+}
@@ -17,7 +17,9 @@ import { process_event, handle_response, view } from "shared/shared";
 import * as types from "shared_types/types/shared_types";
 import * as bincode from "shared_types/bincode/mod";
 import { httpRequest } from "../capabilities/httpRequest";
+import { keyValueRequest } from "../capabilities/keyValueRequest";
 import { locationRequest } from "../capabilities/locationRequest";
+import { notificationRequest } from "../capabilities/notificationRequest";
 
 ChartJS.register(
   CategoryScale,
@@ -106,7 +108,11 @@ interface Event {
 interface Response {
   kind: "response";
   uuid: number[];
-  outcome: types.HttpResponse | types.LocationResponse | types.TimeResponse;
+  outcome:
+    | types.HttpResponse
+    | types.LocationResponse
+    | types.TimeResponse
+    | types.KeyValueResponse;
 }
 
 type State = {
@@ -219,8 +225,8 @@ export default function Index() {
           };
           const mix = isNational ? viewModel.national_mix : viewModel.local_mix;
           const mixPoints = mix.reduce(function (acc, point) {
-            acc[point.fuel] = acc[point.fuel] || [];
-            acc[point.fuel].push(point);
+            acc[point.label] = acc[point.label] || [];
+            acc[point.label].push(point);
             return acc;
           }, {} as Record<string, types.GenerationMixPoint[]>);
           let datasets = Object.entries(mixPoints).map(([label, value]) => {
@@ -275,13 +281,30 @@ export default function Index() {
           break;
         }
 
+        case types.EffectVariantKeyValue: {
+          const request = (effect as types.EffectVariantKeyValue).value;
+          const outcome = keyValueRequest(request);
+          respond({ kind: "response", uuid, outcome });
+          break;
+        }
+
+        case types.EffectVariantNotify: {
+          const request = (effect as types.EffectVariantNotify).value;
+          notificationRequest(request);
+          break;
+        }
+
         default:
       }
     }
   };
 
   useEffect(() => {
-    // Initial event
+    // Initial events
+    dispatch({
+      kind: "event",
+      event: new types.EventVariantStart(),
+    });
     dispatch({
       kind: "event",
       event: new types.EventVariantGetNational(),